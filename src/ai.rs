@@ -0,0 +1,212 @@
+use crate::board::BoardMetrics;
+use crate::game::{Game, GameSnapshot, Shape};
+use crate::input::Action;
+
+// Heuristic weights from Yiyuan Lee's well-known tuned Tetris AI
+const HEIGHT_WEIGHT: f64 = -0.510066;
+const LINES_WEIGHT: f64 = 0.760666;
+const HOLES_WEIGHT: f64 = -0.35663;
+const BUMPINESS_WEIGHT: f64 = -0.184483;
+
+/// Give up steering and just drop after this many moves, in case a kick or an
+/// obstacle keeps the piece from reaching its target
+const MAX_STEPS: u32 = 20;
+
+/// Where a piece should end up: its rotation state and the x of its bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Placement {
+    pub rotation: u8,
+    pub x: i32,
+}
+
+/// Rates a board after a piece has locked; higher is better.
+/// `lines_cleared` counts the rows that placement completed.
+pub fn score_board(board: &[Vec<bool>], lines_cleared: usize) -> f64 {
+    let width = board.first().map_or(0, Vec::len);
+    let metrics = BoardMetrics::measure(width, board.len(), |x, y| board[y][x]);
+
+    HEIGHT_WEIGHT * metrics.aggregate_height() as f64
+        + LINES_WEIGHT * lines_cleared as f64
+        + HOLES_WEIGHT * metrics.total_holes() as f64
+        + BUMPINESS_WEIGHT * metrics.bumpiness() as f64
+}
+
+/// Tries every rotation and column for the current piece, dropping it straight
+/// down from the top, and returns the placement with the best resulting board
+pub fn best_placement(snapshot: &GameSnapshot) -> Option<Placement> {
+    let mut shape = snapshot.shape;
+    let mut rotation = snapshot.rotation;
+    let mut best: Option<(f64, Placement)> = None;
+
+    for _ in 0..4 {
+        let width = snapshot.board[0].len() as i32;
+        for x in -(shape.size() as i32)..width {
+            if let Some((board, lines_cleared)) = drop_shape(&snapshot.board, &shape, x) {
+                let score = score_board(&board, lines_cleared);
+                if best.is_none_or(|(best_score, _)| score > best_score) {
+                    best = Some((score, Placement { rotation, x }));
+                }
+            }
+        }
+
+        shape = shape.rotate_clockwise();
+        rotation = (rotation + 1) % 4;
+    }
+
+    best.map(|(_, placement)| placement)
+}
+
+/// Drops `shape` straight down at box column `x` and returns the board after
+/// clearing full rows, along with how many were cleared. `None` if the shape
+/// doesn't fit at the top.
+fn drop_shape(board: &[Vec<bool>], shape: &Shape, x: i32) -> Option<(Vec<Vec<bool>>, usize)> {
+    let fits = |y: i32| {
+        shape.cells().all(|(col, row)| {
+            let board_x = x + col as i32;
+            let board_y = y + row as i32;
+            board_x >= 0
+                && board_x < board[0].len() as i32
+                && board_y < board.len() as i32
+                && (board_y < 0 || !board[board_y as usize][board_x as usize])
+        })
+    };
+
+    if !fits(0) {
+        return None;
+    }
+    let mut y = 0;
+    while fits(y + 1) {
+        y += 1;
+    }
+
+    let mut result = board.to_vec();
+    for (col, row) in shape.cells() {
+        let board_y = y + row as i32;
+        if board_y >= 0 {
+            result[board_y as usize][(x + col as i32) as usize] = true;
+        }
+    }
+
+    let width = board[0].len();
+    result.retain(|row| !row.iter().all(|&filled| filled));
+    let lines_cleared = board.len() - result.len();
+    for _ in 0..lines_cleared {
+        result.insert(0, vec![false; width]);
+    }
+    Some((result, lines_cleared))
+}
+
+/// Plays the game by steering each new piece to its best placement, one action
+/// per call so the moves are visible
+#[derive(Default)]
+pub struct Autoplayer {
+    /// Pieces spawned so far when the target was chosen, to notice new pieces
+    pieces_seen: u32,
+    target: Option<Placement>,
+    steps: u32,
+}
+
+impl Autoplayer {
+    pub fn next_action(&mut self, game: &Game) -> Option<Action> {
+        if game.paused || game.is_counting_down() || game.is_over() || game.clearing.is_some() {
+            return None;
+        }
+
+        let snapshot = game.snapshot();
+        let pieces_spawned: u32 = game.piece_counts.iter().sum();
+        if pieces_spawned != self.pieces_seen {
+            self.pieces_seen = pieces_spawned;
+            self.target = best_placement(&snapshot);
+            self.steps = 0;
+        }
+
+        let target = self.target?;
+        self.steps += 1;
+        let action = if self.steps > MAX_STEPS {
+            Action::HardDrop
+        } else if snapshot.rotation != target.rotation {
+            Action::RotateCW
+        } else if snapshot.piece_x < target.x {
+            Action::MoveRight
+        } else if snapshot.piece_x > target.x {
+            Action::MoveLeft
+        } else {
+            Action::HardDrop
+        };
+        Some(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BOARD_WIDTH;
+    use crate::game::{Tetromino, TetrominoType};
+
+    /// Builds a board from rows of `#` (filled) and `.` (empty), padded with
+    /// empty rows on top to `height`
+    fn board_from(rows: &[&str], height: usize) -> Vec<Vec<bool>> {
+        let width = rows[0].len();
+        let mut board = vec![vec![false; width]; height - rows.len()];
+        board.extend(
+            rows.iter()
+                .map(|row| row.chars().map(|c| c == '#').collect()),
+        );
+        board
+    }
+
+    #[test]
+    fn empty_board_scores_zero() {
+        assert_eq!(score_board(&board_from(&[".........."], 20), 0), 0.0);
+    }
+
+    #[test]
+    fn holes_and_bumps_lower_the_score() {
+        let flat = board_from(&["##########"], 20);
+        let holey = board_from(&["#.#.#.#.#.", "##########"], 20);
+        let bumpy = board_from(&["#.........", "#.........", "##########"], 20);
+
+        assert!(score_board(&flat, 0) > score_board(&holey, 0));
+        assert!(score_board(&flat, 0) > score_board(&bumpy, 0));
+
+        // Covering a gap turns it into a hole
+        let covered = board_from(&["###", "#.#"], 4);
+        let open = board_from(&["#.#", "###"], 4);
+        assert!(score_board(&open, 0) > score_board(&covered, 0));
+    }
+
+    #[test]
+    fn completed_lines_raise_the_score() {
+        let board = board_from(&["#.#"], 4);
+        assert!(score_board(&board, 1) > score_board(&board, 0));
+    }
+
+    #[test]
+    fn drop_shape_clears_completed_rows() {
+        let board = board_from(&["###.", "###."], 6);
+        let vertical_bar = Shape::from_rows([[true, false], [true, false]]);
+
+        let (result, lines_cleared) = drop_shape(&board, &vertical_bar, 3).unwrap();
+
+        assert_eq!(lines_cleared, 2);
+        assert!(result.iter().flatten().all(|&filled| !filled));
+    }
+
+    #[test]
+    fn picks_vertical_i_for_a_deep_well() {
+        let mut game = Game::with_seed(1);
+        game.current_piece = Tetromino::new(TetrominoType::I, BOARD_WIDTH);
+        let mut snapshot = game.snapshot();
+        snapshot.board = board_from(
+            &["#########.", "#########.", "#########.", "#########."],
+            20,
+        );
+
+        let placement = best_placement(&snapshot).unwrap();
+
+        // Vertical in state R occupies the third column of the 4x4 box
+        assert_eq!(placement.rotation % 2, 1);
+        let column = if placement.rotation == 1 { 2 } else { 1 };
+        assert_eq!(placement.x + column, 9);
+    }
+}
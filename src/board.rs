@@ -0,0 +1,433 @@
+use crate::game::{Shape, TetrominoType, MAX_SHAPE_SIZE};
+use serde::{Deserialize, Serialize};
+
+/// Default playfield size, overridable through `GameConfig`
+pub const BOARD_WIDTH: usize = 10;
+pub const BOARD_HEIGHT: usize = 20;
+/// Hidden rows above the visible field that new pieces spawn into
+pub const BUFFER_ROWS: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Cell {
+    Empty,
+    /// Remembers which piece filled it, so the theme decides its color
+    Filled(TetrominoType),
+    /// Junk pushed up from below by an opponent's attack
+    Garbage,
+}
+
+/// How well built the stack is, column by column, as shown in the side panel and
+/// weighed by the AI
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoardMetrics {
+    /// Height of each column, from the floor to its topmost filled cell
+    pub heights: Vec<usize>,
+    /// Empty cells in each column with a filled cell somewhere above them
+    pub holes: Vec<usize>,
+}
+
+impl BoardMetrics {
+    /// Measures a `width` by `height` stack, row 0 at the top
+    pub fn measure(width: usize, height: usize, is_filled: impl Fn(usize, usize) -> bool) -> Self {
+        let heights: Vec<usize> = (0..width)
+            .map(|x| {
+                (0..height)
+                    .find(|&y| is_filled(x, y))
+                    .map_or(0, |top| height - top)
+            })
+            .collect();
+        let holes = (0..width)
+            .map(|x| {
+                (height - heights[x]..height)
+                    .filter(|&y| !is_filled(x, y))
+                    .count()
+            })
+            .collect();
+        BoardMetrics { heights, holes }
+    }
+
+    pub fn aggregate_height(&self) -> usize {
+        self.heights.iter().sum()
+    }
+
+    pub fn total_holes(&self) -> usize {
+        self.holes.iter().sum()
+    }
+
+    /// Sum of the height differences between neighboring columns
+    pub fn bumpiness(&self) -> usize {
+        self.heights
+            .windows(2)
+            .map(|pair| pair[0].abs_diff(pair[1]))
+            .sum()
+    }
+}
+
+/// Columns of wall on each side of a row's occupancy bits, enough for any piece box
+/// to hang off the edge without its mask shifting out of the `u64`
+const WALL_BITS: usize = MAX_SHAPE_SIZE;
+/// Widest board whose rows, walls included, fit in one `u64`
+pub const MAX_BOARD_WIDTH: usize = u64::BITS as usize - 2 * WALL_BITS;
+
+/// The playfield grid, indexed by `(x, y)` with `y = 0` at the top
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedBoard")]
+pub struct Board {
+    cells: Vec<Vec<Cell>>,
+    /// Occupancy of each row as bits, column `x` at bit `x + WALL_BITS` and the
+    /// walls set, so collision and full-row checks are plain bit operations
+    #[serde(skip)]
+    row_bits: Vec<u64>,
+}
+
+/// What gets saved; the occupancy bits are rebuilt from the cells on load
+#[derive(Deserialize)]
+struct SavedBoard {
+    cells: Vec<Vec<Cell>>,
+}
+
+impl From<SavedBoard> for Board {
+    fn from(saved: SavedBoard) -> Self {
+        Board::from_cells(saved.cells)
+    }
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Self {
+        Board::from_cells(vec![vec![Cell::Empty; width]; height])
+    }
+
+    /// Builds a board from rows of cells, top row first. Panics if the rows are
+    /// wider than `MAX_BOARD_WIDTH`.
+    pub fn from_cells(cells: Vec<Vec<Cell>>) -> Self {
+        let width = cells.first().map_or(0, Vec::len);
+        assert!(
+            width <= MAX_BOARD_WIDTH,
+            "board is wider than {MAX_BOARD_WIDTH}"
+        );
+
+        let row_bits = cells.iter().map(|row| row_bits(row)).collect();
+        Board { cells, row_bits }
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        self.cells[y][x]
+    }
+
+    pub fn is_filled(&self, x: usize, y: usize) -> bool {
+        self.row_bits[y] & (1 << (x + WALL_BITS)) != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, piece_type: TetrominoType) {
+        self.cells[y][x] = Cell::Filled(piece_type);
+        self.row_bits[y] |= 1 << (x + WALL_BITS);
+    }
+
+    /// Turns every cell of row `y` into garbage
+    pub fn fill_row_with_garbage(&mut self, y: usize) {
+        self.cells[y].fill(Cell::Garbage);
+        self.row_bits[y] = u64::MAX;
+    }
+
+    /// Empties the top `count` rows in place, leaving everything below untouched
+    pub fn clear_top_rows(&mut self, count: usize) {
+        let count = count.min(self.height());
+        let empty_row = vec![Cell::Empty; self.width()];
+        for y in 0..count {
+            self.row_bits[y] = row_bits(&empty_row);
+            self.cells[y].clone_from(&empty_row);
+        }
+    }
+
+    pub fn is_row_full(&self, y: usize) -> bool {
+        self.row_bits[y] == u64::MAX
+    }
+
+    /// Whether `shape` with its box's top-left corner at `(x, y)` would overlap a
+    /// filled cell or stick out past the walls, floor or top of the board
+    pub fn collides(&self, shape: &Shape, x: i32, y: i32) -> bool {
+        // A box that far out has every filled cell beyond a wall
+        let Ok(shift) = u32::try_from(x + WALL_BITS as i32) else {
+            return true;
+        };
+        if shift as usize > MAX_BOARD_WIDTH + WALL_BITS {
+            return true;
+        }
+
+        (0..shape.size()).any(|row| {
+            let piece_bits = shape.row_mask(row);
+            if piece_bits == 0 {
+                return false;
+            }
+            let board_y = y + row as i32;
+            if board_y < 0 || board_y >= self.height() as i32 {
+                return true;
+            }
+            self.row_bits[board_y as usize] & (u64::from(piece_bits) << shift) != 0
+        })
+    }
+
+    pub fn full_rows(&self) -> Vec<usize> {
+        (0..self.height())
+            .filter(|&y| self.is_row_full(y))
+            .collect()
+    }
+
+    /// Removes every full row, shifting the rows above down. Returns how many were removed.
+    pub fn clear_full_rows(&mut self) -> usize {
+        let full_rows = self.full_rows();
+
+        // Remove bottom-up so earlier indices stay valid, then refill from the top
+        for &y in full_rows.iter().rev() {
+            self.cells.remove(y);
+            self.row_bits.remove(y);
+        }
+        let empty_row = vec![Cell::Empty; self.width()];
+        for _ in 0..full_rows.len() {
+            self.row_bits.insert(0, row_bits(&empty_row));
+            self.cells.insert(0, empty_row.clone());
+        }
+        full_rows.len()
+    }
+
+    /// Inserts `count` garbage rows at the bottom, each with a hole at `gap_column`,
+    /// pushing everything else up. Returns true if filled cells were pushed off the top.
+    pub fn push_garbage_rows(&mut self, count: usize, gap_column: usize) -> bool {
+        let count = count.min(self.height());
+        let overflowed = self.cells[..count]
+            .iter()
+            .any(|row| row.iter().any(|&cell| cell != Cell::Empty));
+
+        let mut garbage_row = vec![Cell::Garbage; self.width()];
+        if let Some(gap) = garbage_row.get_mut(gap_column) {
+            *gap = Cell::Empty;
+        }
+        self.row_bits.drain(..count);
+        self.row_bits
+            .extend(std::iter::repeat_n(row_bits(&garbage_row), count));
+        self.cells.drain(..count);
+        self.cells.extend(std::iter::repeat_n(garbage_row, count));
+        overflowed
+    }
+
+    /// Lets every filled cell fall on its own until it rests on another filled cell
+    /// or the floor, as used by cascade gravity after a clear. Returns whether
+    /// anything moved.
+    pub fn apply_cascade_gravity(&mut self) -> bool {
+        let mut moved = false;
+        for x in 0..self.width() {
+            // Walk up the column, packing filled cells down onto the lowest free row
+            let mut landing_y = self.height();
+            for y in (0..self.height()).rev() {
+                if self.cells[y][x] != Cell::Empty {
+                    landing_y -= 1;
+                    if landing_y != y {
+                        self.cells[landing_y][x] = self.cells[y][x];
+                        self.cells[y][x] = Cell::Empty;
+                        moved = true;
+                    }
+                }
+            }
+        }
+        if moved {
+            self.row_bits = self.cells.iter().map(|row| row_bits(row)).collect();
+        }
+        moved
+    }
+
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.cells
+    }
+
+    /// Whether not a single cell is filled
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().flatten().all(|&cell| cell == Cell::Empty)
+    }
+
+    pub fn metrics(&self) -> BoardMetrics {
+        BoardMetrics::measure(self.width(), self.height(), |x, y| self.is_filled(x, y))
+    }
+}
+
+/// Occupancy bits for a row of cells, walls included
+fn row_bits(row: &[Cell]) -> u64 {
+    row.iter()
+        .enumerate()
+        .filter(|(_, &cell)| cell != Cell::Empty)
+        .fold(!(((1 << row.len()) - 1) << WALL_BITS), |bits, (x, _)| {
+            bits | 1 << (x + WALL_BITS)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill_row(board: &mut Board, y: usize) {
+        for x in 0..BOARD_WIDTH {
+            board.set(x, y, TetrominoType::Z);
+        }
+    }
+
+    #[test]
+    fn row_is_full_only_when_every_cell_is_filled() {
+        let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        for x in 1..BOARD_WIDTH {
+            board.set(x, 5, TetrominoType::Z);
+        }
+        assert!(!board.is_row_full(5));
+
+        board.set(0, 5, TetrominoType::Z);
+        assert!(board.is_row_full(5));
+    }
+
+    #[test]
+    fn clear_full_rows_counts_and_shifts_rows() {
+        let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        fill_row(&mut board, BOARD_HEIGHT - 1);
+        fill_row(&mut board, BOARD_HEIGHT - 3);
+        board.set(4, BOARD_HEIGHT - 2, TetrominoType::J);
+
+        assert_eq!(board.clear_full_rows(), 2);
+        assert_eq!(
+            board.get(4, BOARD_HEIGHT - 1),
+            Cell::Filled(TetrominoType::J)
+        );
+        assert_eq!(
+            board.rows()[..BOARD_HEIGHT - 1],
+            Board::new(BOARD_WIDTH, BOARD_HEIGHT).rows()[1..]
+        );
+    }
+
+    #[test]
+    fn cascade_gravity_settles_blocks_into_gaps() {
+        let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        let bottom = BOARD_HEIGHT - 1;
+        // A bottom row with a gap at x = 3, a full row above it, and an
+        // overhang on top that covers the gap
+        for x in 0..BOARD_WIDTH {
+            if x != 3 {
+                board.set(x, bottom, TetrominoType::Z);
+            }
+        }
+        fill_row(&mut board, bottom - 1);
+        board.set(2, bottom - 2, TetrominoType::J);
+        board.set(3, bottom - 2, TetrominoType::J);
+
+        assert_eq!(board.clear_full_rows(), 1);
+        // Classic clearing leaves the overhang floating over the gap
+        assert!(!board.is_filled(3, bottom));
+        assert!(board.is_filled(3, bottom - 1));
+
+        assert!(board.apply_cascade_gravity());
+        assert_eq!(board.get(3, bottom), Cell::Filled(TetrominoType::J));
+        assert_eq!(board.get(2, bottom - 1), Cell::Filled(TetrominoType::J));
+        assert!(!board.is_filled(3, bottom - 1));
+        assert!(board.is_row_full(bottom));
+    }
+
+    #[test]
+    fn cascade_gravity_leaves_settled_board_alone() {
+        let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        fill_row(&mut board, BOARD_HEIGHT - 1);
+        board.set(0, BOARD_HEIGHT - 2, TetrominoType::J);
+        let before = board.clone();
+
+        assert!(!board.apply_cascade_gravity());
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn garbage_rows_push_the_stack_up() {
+        let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        board.set(4, BOARD_HEIGHT - 1, TetrominoType::J);
+
+        assert!(!board.push_garbage_rows(2, 7));
+
+        assert_eq!(
+            board.get(4, BOARD_HEIGHT - 3),
+            Cell::Filled(TetrominoType::J)
+        );
+        for y in [BOARD_HEIGHT - 2, BOARD_HEIGHT - 1] {
+            assert_eq!(board.get(7, y), Cell::Empty);
+            assert_eq!(board.get(0, y), Cell::Garbage);
+            assert!(!board.is_row_full(y));
+        }
+    }
+
+    #[test]
+    fn garbage_reports_overflow_off_the_top() {
+        let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        board.set(0, 0, TetrominoType::I);
+
+        assert!(board.push_garbage_rows(1, 0));
+    }
+
+    #[test]
+    fn metrics_measure_heights_holes_and_bumps() {
+        let mut board = Board::new(4, 6);
+        // Column 0 is three high over a hole, column 2 one high, the rest empty
+        board.set(0, 3, TetrominoType::Z);
+        board.set(0, 5, TetrominoType::Z);
+        board.set(2, 5, TetrominoType::Z);
+
+        let metrics = board.metrics();
+
+        assert_eq!(metrics.heights, vec![3, 0, 1, 0]);
+        assert_eq!(metrics.holes, vec![1, 0, 0, 0]);
+        assert_eq!(metrics.aggregate_height(), 4);
+        assert_eq!(metrics.total_holes(), 1);
+        assert_eq!(metrics.bumpiness(), 3 + 1 + 1);
+    }
+
+    #[test]
+    fn collides_with_walls_floor_and_stack() {
+        let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        board.set(5, BOARD_HEIGHT - 1, TetrominoType::Z);
+        // A vertical I sits in column 2 of its box
+        let vertical_i = TetrominoType::I.shape().rotate_clockwise();
+
+        assert!(!board.collides(&vertical_i, -2, 0));
+        assert!(board.collides(&vertical_i, -3, 0));
+        assert!(!board.collides(&vertical_i, BOARD_WIDTH as i32 - 3, 0));
+        assert!(board.collides(&vertical_i, BOARD_WIDTH as i32 - 2, 0));
+        assert!(board.collides(&vertical_i, 0, -1));
+        assert!(!board.collides(&vertical_i, 0, BOARD_HEIGHT as i32 - 4));
+        assert!(board.collides(&vertical_i, 0, BOARD_HEIGHT as i32 - 3));
+        assert!(board.collides(&vertical_i, 3, BOARD_HEIGHT as i32 - 4));
+        // Far past either wall
+        assert!(board.collides(&vertical_i, -40, 0));
+        assert!(board.collides(&vertical_i, 100, 0));
+    }
+
+    #[test]
+    fn occupancy_is_rebuilt_after_loading() {
+        let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        fill_row(&mut board, BOARD_HEIGHT - 1);
+        board.set(3, BOARD_HEIGHT - 2, TetrominoType::J);
+
+        let json = serde_json::to_string(&board).unwrap();
+        let loaded: Board = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded, board);
+        assert!(loaded.is_row_full(BOARD_HEIGHT - 1));
+        assert!(loaded.is_filled(3, BOARD_HEIGHT - 2));
+    }
+
+    #[test]
+    fn clear_full_rows_includes_top_row() {
+        let mut board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        fill_row(&mut board, 0);
+
+        assert_eq!(board.clear_full_rows(), 1);
+        assert_eq!(board, Board::new(BOARD_WIDTH, BOARD_HEIGHT));
+    }
+}
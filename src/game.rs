@@ -0,0 +1,4140 @@
+use crate::board::{Board, Cell, BOARD_HEIGHT, BOARD_WIDTH, BUFFER_ROWS};
+use crate::input::Action;
+use crate::randomizer::Randomizer;
+use crate::serde_instant;
+use crate::settings::app_dir;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const TICK_RATE: Duration = Duration::from_millis(500);
+const MIN_TICK_RATE: Duration = Duration::from_millis(50);
+const LINES_PER_LEVEL: u32 = 10;
+const LOCK_DELAY: Duration = Duration::from_millis(500);
+/// Lock delay restarts allowed per piece unless configured otherwise
+pub const MAX_LOCK_RESETS: u32 = 15;
+/// Scoring entries kept in `Game::score_log`
+const SCORE_LOG_LEN: usize = 20;
+/// Rows emptied off the top of the board, buffer included, when a zen game would
+/// top out. Enough for any piece to spawn.
+const ZEN_CLEARED_ROWS: usize = BUFFER_ROWS + MAX_SHAPE_SIZE;
+/// Locks a zen game remembers for undoing, oldest dropped first
+const UNDO_LIMIT: usize = 50;
+/// Frame rate of the NTSC NES, which its gravity table counts in
+const NES_FRAME_RATE: f64 = 60.0988;
+/// Guideline gravity tops out at 20G, a full 20-row drop every 60Hz frame
+const GUIDELINE_MAX_LEVEL: u32 = 20;
+const GUIDELINE_MIN_TICK: f64 = 1.0 / 60.0 / 20.0;
+pub const SPRINT_LINES: u32 = 40;
+/// Lines that complete a marathon unless configured otherwise
+pub const MARATHON_LINES: u32 = 150;
+/// Flat bonus for a clear that leaves the board empty
+pub const PERFECT_CLEAR_BONUS: u32 = 3000;
+/// Time between garbage rows rising in a survival game at rise level 0
+const SURVIVAL_RISE_INTERVAL: Duration = Duration::from_secs(8);
+/// The fastest garbage ever rises in a survival game
+const SURVIVAL_MIN_RISE_INTERVAL: Duration = Duration::from_millis(1000);
+/// Rows risen in a survival game before the rise level goes up
+const SURVIVAL_RISES_PER_LEVEL: u32 = 5;
+/// Points for outlasting a rising row, times the rise level plus one
+const SURVIVAL_RISE_POINTS: u32 = 50;
+/// Most pieces `--hold` can set aside at once, as many as the panel has room for
+pub const MAX_HOLD_CAPACITY: usize = 4;
+/// Garbage rows a dig game starts on unless configured otherwise
+pub const DIG_GARBAGE_ROWS: usize = 10;
+/// Highest level a game can start at, past where the linear curve stops speeding up
+pub const MAX_START_LEVEL: u32 = 15;
+pub const LINE_CLEAR_DELAY: Duration = Duration::from_millis(150);
+/// Length of the 3-2-1 countdown before play starts or resumes
+pub const COUNTDOWN: Duration = Duration::from_secs(3);
+/// How long topping out takes to fill the board before the game-over screen
+pub const TOP_OUT_DURATION: Duration = Duration::from_secs(1);
+/// Game time advanced by one `Game::step`. `update` only ever runs whole steps, so
+/// a game plays out the same however its time is split between frames.
+pub const SIMULATION_STEP: Duration = Duration::from_millis(1);
+
+/// A kind of piece: one entry of the built-in [`PIECES`] table. The tetrominoes have
+/// constants of their own; the pentominoes are reached through
+/// [`TetrominoType::PENTOMINOES`] or [`TetrominoType::by_name`]. Saved by name, so
+/// games saved before the table existed still load.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "&'static str", try_from = "String")]
+pub struct TetrominoType(u8);
+
+/// How a kind of piece looks and is drawn
+pub struct PieceDef {
+    pub name: &'static str,
+    /// Slot of the theme's piece palette it is drawn in
+    pub color: usize,
+    /// Spawn orientation inside the piece's bounding box. Rotations turn the whole
+    /// box, so pieces in an odd-sized box pivot on its center cell and never drift.
+    pub shape: Shape,
+}
+
+/// Every piece the game knows: the seven tetrominoes in SRS boxes (4×4 for I, 2×2
+/// for O and 3×3 for the rest) in their Guideline spawn orientations, flat side
+/// down, then the twelve pentominoes
+pub const PIECES: [PieceDef; 19] = {
+    const X: bool = true;
+    const O: bool = false;
+    const fn piece(name: &'static str, color: usize, shape: Shape) -> PieceDef {
+        PieceDef { name, color, shape }
+    }
+    [
+        piece(
+            "I",
+            0,
+            Shape::from_rows([[O, O, O, O], [X, X, X, X], [O, O, O, O], [O, O, O, O]]),
+        ),
+        piece("O", 1, Shape::from_rows([[X, X], [X, X]])),
+        piece("T", 2, Shape::from_rows([[O, X, O], [X, X, X], [O, O, O]])),
+        piece("L", 3, Shape::from_rows([[O, O, X], [X, X, X], [O, O, O]])),
+        piece("J", 4, Shape::from_rows([[X, O, O], [X, X, X], [O, O, O]])),
+        piece("S", 5, Shape::from_rows([[O, X, X], [X, X, O], [O, O, O]])),
+        piece("Z", 6, Shape::from_rows([[X, X, O], [O, X, X], [O, O, O]])),
+        piece("F5", 6, Shape::from_rows([[O, X, X], [X, X, O], [O, X, O]])),
+        piece(
+            "I5",
+            0,
+            Shape::from_rows([
+                [O, O, O, O, O],
+                [O, O, O, O, O],
+                [X, X, X, X, X],
+                [O, O, O, O, O],
+                [O, O, O, O, O],
+            ]),
+        ),
+        piece(
+            "L5",
+            3,
+            Shape::from_rows([[O, O, O, X], [X, X, X, X], [O, O, O, O], [O, O, O, O]]),
+        ),
+        piece(
+            "N5",
+            5,
+            Shape::from_rows([[X, X, O, O], [O, X, X, X], [O, O, O, O], [O, O, O, O]]),
+        ),
+        piece("P5", 1, Shape::from_rows([[O, X, X], [X, X, X], [O, O, O]])),
+        piece("T5", 2, Shape::from_rows([[X, X, X], [O, X, O], [O, X, O]])),
+        piece("U5", 1, Shape::from_rows([[X, O, X], [X, X, X], [O, O, O]])),
+        piece("V5", 4, Shape::from_rows([[X, O, O], [X, O, O], [X, X, X]])),
+        piece("W5", 6, Shape::from_rows([[X, O, O], [X, X, O], [O, X, X]])),
+        piece("X5", 2, Shape::from_rows([[O, X, O], [X, X, X], [O, X, O]])),
+        piece(
+            "Y5",
+            4,
+            Shape::from_rows([[O, O, X, O], [X, X, X, X], [O, O, O, O], [O, O, O, O]]),
+        ),
+        piece("Z5", 5, Shape::from_rows([[X, X, O], [O, X, O], [O, X, X]])),
+    ]
+};
+
+impl TetrominoType {
+    pub const I: TetrominoType = TetrominoType(0);
+    pub const O: TetrominoType = TetrominoType(1);
+    pub const T: TetrominoType = TetrominoType(2);
+    pub const L: TetrominoType = TetrominoType(3);
+    pub const J: TetrominoType = TetrominoType(4);
+    pub const S: TetrominoType = TetrominoType(5);
+    pub const Z: TetrominoType = TetrominoType(6);
+
+    pub const TETROMINOES: [TetrominoType; 7] = [
+        TetrominoType::I,
+        TetrominoType::O,
+        TetrominoType::T,
+        TetrominoType::L,
+        TetrominoType::J,
+        TetrominoType::S,
+        TetrominoType::Z,
+    ];
+
+    /// F, I, L, N, P, T, U, V, W, X, Y and Z, each five cells
+    pub const PENTOMINOES: [TetrominoType; 12] = {
+        let mut pieces = [TetrominoType(0); 12];
+        let mut i = 0;
+        while i < pieces.len() {
+            pieces[i] = TetrominoType((TetrominoType::TETROMINOES.len() + i) as u8);
+            i += 1;
+        }
+        pieces
+    };
+
+    /// Position of this type within [`PIECES`]
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn by_name(name: &str) -> Option<TetrominoType> {
+        PIECES
+            .iter()
+            .position(|piece| piece.name == name)
+            .map(|index| TetrominoType(index as u8))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.definition().name
+    }
+
+    /// Slot of the theme's piece palette this type is drawn in
+    pub fn color(&self) -> usize {
+        self.definition().color
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.definition().shape
+    }
+
+    fn definition(&self) -> &'static PieceDef {
+        &PIECES[self.index()]
+    }
+}
+
+impl std::fmt::Debug for TetrominoType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl From<TetrominoType> for &'static str {
+    fn from(piece_type: TetrominoType) -> Self {
+        piece_type.name()
+    }
+}
+
+impl TryFrom<String> for TetrominoType {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        TetrominoType::by_name(&name).ok_or_else(|| format!("unknown piece {name:?}"))
+    }
+}
+
+/// Which pieces the randomizer deals
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PieceSet {
+    Tetrominoes,
+    Pentominoes,
+}
+
+impl PieceSet {
+    pub fn pieces(&self) -> &'static [TetrominoType] {
+        match self {
+            PieceSet::Tetrominoes => &TetrominoType::TETROMINOES,
+            PieceSet::Pentominoes => &TetrominoType::PENTOMINOES,
+        }
+    }
+
+    /// Side of the largest box among the pieces, so the smallest board they all fit
+    pub fn max_shape_size(&self) -> usize {
+        self.pieces()
+            .iter()
+            .map(|piece_type| piece_type.shape().size())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Side of the grid every shape's box fits in, 5 for the pentomino I
+pub const MAX_SHAPE_SIZE: usize = 5;
+
+/// One orientation of a piece within its square bounding box, as a mask over a 5×5
+/// grid (the largest box any piece needs). Cell `(x, y)` is bit `y * 5 + x`, so each
+/// row of the box is five bits ready to shift onto a board row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shape {
+    mask: u32,
+    /// Side length of the bounding box; cells outside it are always empty
+    size: u8,
+}
+
+impl Shape {
+    /// Builds a shape from the rows of an `N`×`N` box, `N` being at most
+    /// `MAX_SHAPE_SIZE`
+    pub const fn from_rows<const N: usize>(rows: [[bool; N]; N]) -> Shape {
+        assert!(N <= MAX_SHAPE_SIZE, "shapes fit in a 5x5 box");
+        let mut mask = 0;
+        let mut y = 0;
+        while y < N {
+            let mut x = 0;
+            while x < N {
+                if rows[y][x] {
+                    mask |= 1 << (y * MAX_SHAPE_SIZE + x);
+                }
+                x += 1;
+            }
+            y += 1;
+        }
+        Shape {
+            mask,
+            size: N as u8,
+        }
+    }
+
+    /// The box as rows of booleans, top row first, for drawing
+    pub fn to_rows(&self) -> Vec<Vec<bool>> {
+        (0..self.size())
+            .map(|y| (0..self.size()).map(|x| self.is_filled(x, y)).collect())
+            .collect()
+    }
+
+    /// Side length of the bounding box
+    pub fn size(&self) -> usize {
+        self.size as usize
+    }
+
+    pub fn is_filled(&self, x: usize, y: usize) -> bool {
+        self.mask & (1 << (y * MAX_SHAPE_SIZE + x)) != 0
+    }
+
+    /// Row `y` of the box with column `x` at bit `x`
+    pub fn row_mask(&self, y: usize) -> u16 {
+        ((self.mask >> (y * MAX_SHAPE_SIZE)) & 0b11111) as u16
+    }
+
+    /// Box coordinates `(x, y)` of the filled cells
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..MAX_SHAPE_SIZE * MAX_SHAPE_SIZE)
+            .filter(|bit| self.mask & (1 << bit) != 0)
+            .map(|bit| (bit % MAX_SHAPE_SIZE, bit / MAX_SHAPE_SIZE))
+    }
+
+    pub fn rotate_clockwise(&self) -> Shape {
+        let n = self.size();
+        self.remap(|x, y| (n - 1 - y, x))
+    }
+
+    pub fn rotate_counter_clockwise(&self) -> Shape {
+        let n = self.size();
+        self.remap(|x, y| (y, n - 1 - x))
+    }
+
+    pub fn rotate_180(&self) -> Shape {
+        let n = self.size();
+        self.remap(|x, y| (n - 1 - x, n - 1 - y))
+    }
+
+    /// Moves every filled cell to where `to` sends it within the same box
+    fn remap(&self, to: impl Fn(usize, usize) -> (usize, usize)) -> Shape {
+        let mask = self.cells().fold(0, |mask, (x, y)| {
+            let (x, y) = to(x, y);
+            mask | 1 << (y * MAX_SHAPE_SIZE + x)
+        });
+        Shape { mask, ..*self }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TSpin {
+    Mini,
+    Full,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tetromino {
+    pub piece_type: TetrominoType,
+    pub shape: Shape,
+    pub x: i32,
+    pub y: i32,
+    /// SRS rotation state: 0 = spawn, 1 = R, 2 = 180, 3 = L
+    pub rotation: u8,
+}
+
+impl Tetromino {
+    /// Creates a piece in spawn orientation, centered horizontally on a board of `board_width`
+    pub fn new(piece_type: TetrominoType, board_width: usize) -> Self {
+        let shape = Tetromino::spawn_shape(piece_type);
+        let width = shape.size() as i32;
+
+        Tetromino {
+            piece_type,
+            shape,
+            x: (board_width as i32 - width) / 2,
+            y: 0,
+            rotation: 0,
+        }
+    }
+
+    /// The orientation every piece of `piece_type` starts in, SRS state 0. Previews
+    /// draw this so the next and held pieces look exactly as they'll come in.
+    pub fn spawn_shape(piece_type: TetrominoType) -> Shape {
+        piece_type.shape()
+    }
+
+    pub(crate) fn rotate_clockwise(&self) -> Shape {
+        self.shape.rotate_clockwise()
+    }
+
+    fn rotate_counter_clockwise(&self) -> Shape {
+        self.shape.rotate_counter_clockwise()
+    }
+
+    fn rotate_180(&self) -> Shape {
+        self.shape.rotate_180()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Endless play with increasing levels
+    Marathon,
+    /// Clear SPRINT_LINES lines as fast as possible
+    Sprint,
+    /// Start on rows of garbage and dig down through all of them as fast as possible
+    Dig,
+    /// Garbage rows rise from the bottom faster and faster; survive as long as possible
+    Survival,
+    /// Endless play that never tops out: the top of the stack is cleared away
+    /// instead, and scores don't count towards the high score
+    Zen,
+}
+
+/// How blocks above a cleared row come down
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GravityMode {
+    /// Rows above shift down as a whole, the standard behavior
+    Classic,
+    /// Every block falls on its own into any gap below, clearing again if that
+    /// completes more rows
+    Cascade,
+}
+
+/// Speed curve giving the time between gravity ticks, one row each, for a level.
+/// Speeds are quoted in G, rows fallen per 60Hz frame.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GravityTable {
+    /// 500ms per row at level 0, 40ms faster per level down to 50ms from level 12.
+    /// This game's own curve.
+    Linear,
+    /// NES (NTSC) frames per row, at 60.0988 frames per second:
+    ///
+    /// | Level | Frames | G      |
+    /// |-------|--------|--------|
+    /// | 0     | 48     | 0.021  |
+    /// | 1–8   | 43 down to 8, 5 fewer per level | 0.023–0.125 |
+    /// | 9     | 6      | 0.167  |
+    /// | 10–12 | 5      | 0.2    |
+    /// | 13–15 | 4      | 0.25   |
+    /// | 16–18 | 3      | 0.333  |
+    /// | 19–28 | 2      | 0.5    |
+    /// | 29+   | 1      | 1      |
+    Nes,
+    /// Tetris Guideline: `(0.8 - (n - 1) * 0.007)^(n - 1)` seconds per row at
+    /// guideline level `n`, which is this game's level plus one. That is 1s (0.017G)
+    /// at level 0, 0.79s (0.021G) at 1, 64ms (0.26G) at 9, 7.1ms (2.4G) at 14, and
+    /// 20G from level 18 on.
+    Guideline,
+}
+
+impl GravityTable {
+    pub fn tick_rate(self, level: u32) -> Duration {
+        match self {
+            GravityTable::Linear => TICK_RATE
+                .saturating_sub(Duration::from_millis(level as u64 * 40))
+                .max(MIN_TICK_RATE),
+            GravityTable::Nes => {
+                let frames = match level {
+                    0 => 48,
+                    1..=8 => 48 - 5 * level,
+                    9 => 6,
+                    10..=12 => 5,
+                    13..=15 => 4,
+                    16..=18 => 3,
+                    19..=28 => 2,
+                    _ => 1,
+                };
+                Duration::from_secs_f64(frames as f64 / NES_FRAME_RATE)
+            }
+            GravityTable::Guideline => {
+                // Capped before the formula's base turns negative for huge levels
+                let n = (level + 1).min(GUIDELINE_MAX_LEVEL) as f64;
+                let seconds = (0.8 - (n - 1.0) * 0.007).powf(n - 1.0);
+                Duration::from_secs_f64(seconds.max(GUIDELINE_MIN_TICK))
+            }
+        }
+    }
+}
+
+/// Presets bundling the settings that make the game easier or harder
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    /// The slow-starting guideline curve with a second to place each grounded piece
+    Easy,
+    /// The standard linear curve and half-second lock delay from level 0
+    Normal,
+    /// NES speeds from level 10, a short lock delay and no ghost piece
+    Hard,
+}
+
+/// A day on the UTC calendar, naming a daily challenge
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today() -> Date {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        Date::from_days_since_epoch((seconds / 86_400) as i64)
+    }
+
+    /// The day `days` after 1970-01-01, by Howard Hinnant's `civil_from_days`
+    pub fn from_days_since_epoch(days: i64) -> Date {
+        // Counted from 0000-03-01 so the leap day falls at the end of the year
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = (year_of_era + era * 400) as i32 + i32::from(month <= 2);
+        Date { year, month, day }
+    }
+
+    /// The date as the number YYYYMMDD, which the RNG's seeding scrambles into a
+    /// sequence of its own. Every player gets the same one on the same day.
+    pub fn seed(&self) -> u64 {
+        (self.year as i64 * 10_000 + self.month as i64 * 100 + self.day as i64) as u64
+    }
+}
+
+impl std::fmt::Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Settings fixed for the lifetime of a game. Anything missing from an older save
+/// takes its default.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub mode: GameMode,
+    pub gravity: GravityMode,
+    pub width: usize,
+    /// Visible rows; the board adds BUFFER_ROWS hidden ones on top for spawning
+    pub height: usize,
+    /// Fixed seed for a reproducible piece sequence, random when `None`
+    pub seed: Option<u64>,
+    /// Day of the daily challenge this game is, whose date gave the seed
+    pub daily: Option<Date>,
+    /// How many upcoming pieces the next queue shows
+    pub preview_count: usize,
+    /// Lines that win a marathon, or `None` to play until topping out
+    pub marathon_goal: Option<u32>,
+    /// Garbage rows a dig game starts on
+    pub garbage_rows: usize,
+    /// Level the game begins at; clearing lines never takes it lower
+    pub start_level: u32,
+    /// How fast pieces fall at each level
+    pub gravity_table: GravityTable,
+    /// Fill the board row by row after topping out, before the game-over screen
+    pub top_out_animation: bool,
+    /// Preset the level, speed, lock delay and ghost settings came from
+    pub difficulty: Difficulty,
+    /// How long a grounded piece can still slide or rotate before it locks
+    pub lock_delay: Duration,
+    /// Moves and rotations per piece that restart the lock delay, after which it
+    /// runs out regardless, so a piece can't be spun in place forever
+    pub max_lock_resets: u32,
+    /// Whether to draw where the piece will land
+    pub show_ghost: bool,
+    /// Pieces the randomizer deals from
+    pub piece_set: PieceSet,
+    /// How the next piece is picked, a shuffled bag of one of each by default
+    pub randomizer: Randomizer,
+    /// When set, a hard drop only goes through if pressed again within this long;
+    /// the first press soft drops instead, guarding against misdrops
+    pub confirm_hard_drop: Option<Duration>,
+    /// Count the pieces placed with more moves and rotations than they needed
+    pub finesse: bool,
+    /// Pieces that can be held at once. Holding with the hold full brings back the
+    /// one held longest, so 1 is the standard swap.
+    pub hold_capacity: usize,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            mode: GameMode::Marathon,
+            gravity: GravityMode::Classic,
+            width: BOARD_WIDTH,
+            height: BOARD_HEIGHT,
+            seed: None,
+            daily: None,
+            preview_count: 5,
+            marathon_goal: Some(MARATHON_LINES),
+            garbage_rows: DIG_GARBAGE_ROWS,
+            start_level: 0,
+            gravity_table: GravityTable::Linear,
+            top_out_animation: true,
+            difficulty: Difficulty::Normal,
+            lock_delay: LOCK_DELAY,
+            max_lock_resets: MAX_LOCK_RESETS,
+            show_ghost: true,
+            piece_set: PieceSet::Tetrominoes,
+            randomizer: Randomizer::default(),
+            confirm_hard_drop: None,
+            finesse: false,
+            hold_capacity: 1,
+        }
+    }
+}
+
+impl GameConfig {
+    /// The defaults, with the starting level, gravity table, lock delay and ghost
+    /// set by `difficulty`
+    pub fn with_difficulty(difficulty: Difficulty) -> Self {
+        let (start_level, gravity_table, lock_delay, show_ghost) = match difficulty {
+            Difficulty::Easy => (0, GravityTable::Guideline, Duration::from_secs(1), true),
+            Difficulty::Normal => (0, GravityTable::Linear, LOCK_DELAY, true),
+            Difficulty::Hard => (10, GravityTable::Nes, Duration::from_millis(300), false),
+        };
+        GameConfig {
+            difficulty,
+            start_level,
+            gravity_table,
+            lock_delay,
+            show_ghost,
+            ..GameConfig::default()
+        }
+    }
+
+    /// Lines that finish the game as a win in this mode, if any
+    pub fn line_goal(&self) -> Option<u32> {
+        match self.mode {
+            GameMode::Marathon => self.marathon_goal,
+            GameMode::Sprint => Some(SPRINT_LINES),
+            GameMode::Dig | GameMode::Survival | GameMode::Zen => None,
+        }
+    }
+}
+
+/// Whether the pieces are live yet
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameState {
+    /// Counting down for the given time so far, before the first piece falls or
+    /// after resuming from a pause. Gravity and moves wait until `COUNTDOWN` is up.
+    Countdown(Duration),
+    Playing,
+    /// Topped out at the given instant and filling the board with garbage from the
+    /// bottom up, one row at a time over `TOP_OUT_DURATION`. Returns to `Playing`
+    /// once full, leaving `game_over` to tell the two apart.
+    GameOverAnimation(#[serde(with = "serde_instant")] Instant),
+}
+
+/// Complete game state. Serializes to JSON in full, including the RNG, so a
+/// loaded game continues exactly where it was saved.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Game {
+    pub config: GameConfig,
+    pub state: GameState,
+    pub board: Board,
+    pub current_piece: Tetromino,
+    /// Upcoming pieces, front first, always holding at least `preview_count` entries
+    pub next_queue: VecDeque<TetrominoType>,
+    /// Pieces set aside, held longest first, at most `hold_capacity` of them. Saves
+    /// from before there could be several start with nothing held.
+    #[serde(default)]
+    pub held: VecDeque<TetrominoType>,
+    pub can_hold: bool,
+    /// How many of each piece type have spawned, indexed by `TetrominoType::index`.
+    /// Saves from before the pentominoes only count the tetrominoes.
+    pub piece_counts: Vec<u32>,
+    /// Deals the pieces, with whatever it remembers of those dealt so far. Saves
+    /// from before randomizers were configurable start on a fresh bag.
+    #[serde(default)]
+    pub randomizer: Randomizer,
+    /// Seed the piece sequence was generated from, even if none was configured
+    pub seed: u64,
+    /// Same generator as `StdRng`, but serializable
+    pub rng: ChaCha12Rng,
+    /// Game time not yet consumed by gravity ticks
+    pub gravity_accumulator: Duration,
+    /// How long the piece has been resting on the stack, set while it does and
+    /// counting towards the lock delay
+    pub lock_timer: Option<Duration>,
+    pub lock_resets: u32,
+    /// Rows being flashed before removal, and how long the animation has run
+    pub clearing: Option<(Vec<usize>, Duration)>,
+    pub game_over: bool,
+    /// Set when the mode's goal is reached, as opposed to topping out
+    pub won: bool,
+    /// Time played in whole simulation steps, standing still while paused or
+    /// counting down and once the game ends. Everything timed during play runs on
+    /// it rather than the wall clock.
+    #[serde(default)]
+    clock: Duration,
+    /// Time passed to `update` that doesn't make up a whole step yet
+    #[serde(skip)]
+    unsimulated: Duration,
+    pub paused: bool,
+    pub score: u32,
+    /// Best score so far, which the game only raises. Loading and saving it is left
+    /// to the caller.
+    pub high_score: u32,
+    pub drop_points: u32,
+    /// Whether the last line clear was a tetris, enabling the back-to-back bonus
+    pub back_to_back: bool,
+    /// Number of consecutive clearing locks after the first, -1 when no chain is active
+    pub combo: i32,
+    /// Longest combo reached this game
+    pub max_combo: u32,
+    /// Set by rotations and cleared by moves, so only a rotation into place counts as a T-spin
+    pub last_move_was_rotation: bool,
+    /// Most recent T-spin and when it happened, for on-screen feedback
+    #[serde(with = "serde_instant::option_pair")]
+    pub last_t_spin: Option<(TSpin, Instant)>,
+    /// When the board was last cleared to empty, for the banner over it
+    #[serde(with = "serde_instant::option")]
+    pub last_perfect_clear: Option<Instant>,
+    /// When the level last went up, to flash the level indicator
+    #[serde(with = "serde_instant::option")]
+    pub last_level_up: Option<Instant>,
+    /// When the piece was last swapped with the held one, to highlight the hold box
+    #[serde(with = "serde_instant::option")]
+    pub last_hold: Option<Instant>,
+    /// How long ago hard drop was pressed without going through, waiting for the
+    /// second press that confirms it
+    pub last_hard_drop_press: Option<Duration>,
+    /// Most recent hard drop and when it happened, for the trail drawn behind it
+    #[serde(with = "serde_instant::option_pair")]
+    pub last_hard_drop: Option<(DropTrail, Instant)>,
+    pub level: u32,
+    pub lines: u32,
+    /// Pieces locked into the stack, for the end-of-game summary
+    pub pieces_placed: u32,
+    /// Most recent scoring, oldest first, for the breakdown in the side panel
+    pub score_log: VecDeque<ScoreEntry>,
+    /// Garbage the most recent lock would send to an opponent
+    lines_sent: u32,
+    /// Moves and rotations the player has made with the current piece
+    pub piece_inputs: u32,
+    /// Pieces placed with more inputs than the fewest that reach the same spot,
+    /// counted when `finesse` is on
+    pub finesse_faults: u32,
+    /// How fast garbage rises in a survival game, going up every
+    /// `SURVIVAL_RISES_PER_LEVEL` rows
+    #[serde(default)]
+    pub rise_level: u32,
+    /// Game time since the last garbage row rose in a survival game
+    #[serde(default)]
+    pub rise_timer: Duration,
+    /// Garbage rows risen so far in a survival game
+    #[serde(default)]
+    pub rises: u32,
+    /// Happenings since the last `update` or `handle_action` returned them
+    #[serde(skip)]
+    events: Vec<GameEvent>,
+    /// The game as it was before each recent lock, newest last, for undoing in zen
+    /// mode. Not saved, so a loaded game starts with nothing to undo.
+    #[serde(skip)]
+    undo_history: VecDeque<Game>,
+}
+
+/// Something that happened during play, for the caller to react to with sound or
+/// effects, or to pass on to a versus opponent
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameEvent {
+    Rotated,
+    HardDropped,
+    PieceLocked,
+    LinesCleared(u32),
+    /// Reported with the lock that scored it, whether or not it cleared lines
+    TSpin(TSpin),
+    /// The level went up, to the one given
+    LevelUp(u32),
+    /// A clear left the board completely empty
+    PerfectClear,
+    /// Points were awarded; soft drops report every cell separately
+    Scored(ScoreEntry),
+    /// The lock sends this many garbage lines to a versus opponent
+    GarbageSent(u32),
+    GameOver,
+}
+
+/// What a piece of the score was awarded for
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ScoreReason {
+    SoftDrop,
+    HardDrop,
+    /// A lock clearing `lines` rows, or none for a T-spin without lines
+    Clear {
+        lines: u32,
+        t_spin: Option<TSpin>,
+        /// Scored with the 1.5x back-to-back bonus
+        back_to_back: bool,
+    },
+    /// The given step of a combo
+    Combo(u32),
+    /// A clear that emptied the board
+    PerfectClear,
+    /// Outlasting another garbage row rising in survival mode
+    Survived,
+}
+
+/// One line of the score log
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    /// Which piece of the game scored it, counting from 1
+    pub piece: u32,
+    pub reason: ScoreReason,
+    pub points: u32,
+}
+
+/// Cells a hard-dropped piece swept through: every row from the piece's top before
+/// the drop down to its top after, across the columns it spans. Purely cosmetic.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DropTrail {
+    pub left: i32,
+    pub right: i32,
+    /// Top row of the piece before the drop
+    pub from_y: i32,
+    /// Top row of the piece where it landed
+    pub to_y: i32,
+}
+
+impl DropTrail {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (self.left..=self.right).contains(&x) && (self.from_y..self.to_y).contains(&y)
+    }
+}
+
+/// Plain copy of the state a bot, test or spectator needs, free of timers and
+/// terminal types
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    /// Filled cells of the locked stack, indexed `[y][x]`, excluding the falling piece.
+    /// The first `BUFFER_ROWS` rows are the hidden spawn area.
+    pub board: Vec<Vec<bool>>,
+    pub piece_type: TetrominoType,
+    pub piece_x: i32,
+    pub piece_y: i32,
+    pub rotation: u8,
+    pub shape: Shape,
+    /// Upcoming pieces, front first
+    pub next: Vec<TetrominoType>,
+    /// Held pieces, the one to come back first first
+    pub held: Vec<TetrominoType>,
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+    pub game_over: bool,
+}
+
+impl Game {
+    /// Rotation methods return `Rotated` if the piece turned, or nothing if it couldn't
+    pub fn rotate_piece(&mut self) -> Vec<GameEvent> {
+        let rotated_shape = self.current_piece.rotate_clockwise();
+        self.try_rotation(rotated_shape, (self.current_piece.rotation + 1) % 4);
+        self.take_events()
+    }
+
+    pub fn rotate_piece_ccw(&mut self) -> Vec<GameEvent> {
+        let rotated_shape = self.current_piece.rotate_counter_clockwise();
+        self.try_rotation(rotated_shape, (self.current_piece.rotation + 3) % 4);
+        self.take_events()
+    }
+
+    pub fn rotate_piece_180(&mut self) -> Vec<GameEvent> {
+        let rotated_shape = self.current_piece.rotate_180();
+        self.try_rotation(rotated_shape, (self.current_piece.rotation + 2) % 4);
+        self.take_events()
+    }
+
+    /// Applies the first SRS kick offset that fits; if none do, the rotation is not performed
+    fn try_rotation(&mut self, rotated_shape: Shape, new_rotation: u8) {
+        let kicks = srs_kicks(
+            self.current_piece.piece_type,
+            self.current_piece.rotation,
+            new_rotation,
+        );
+
+        for &(dx, dy) in kicks {
+            // Kick tables use y-up, the board uses y-down
+            let x = self.current_piece.x + dx;
+            let y = self.current_piece.y - dy;
+            if self.is_valid_position(&rotated_shape, x, y) {
+                self.current_piece.shape = rotated_shape;
+                self.current_piece.x = x;
+                self.current_piece.y = y;
+                self.current_piece.rotation = new_rotation;
+                self.last_move_was_rotation = true;
+                self.reset_lock_timer();
+                self.events.push(GameEvent::Rotated);
+                return;
+            }
+        }
+    }
+
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self::with_config(GameConfig::with_difficulty(difficulty))
+    }
+
+    /// Creates a game whose piece sequence is fully determined by `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_config(GameConfig {
+            seed: Some(seed),
+            ..GameConfig::default()
+        })
+    }
+
+    pub fn with_config(config: GameConfig) -> Self {
+        let start_level = config.start_level;
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        let pieces = config.piece_set.pieces();
+        let mut randomizer = config.randomizer.clone();
+        let current_piece = Tetromino::new(randomizer.next(pieces, &mut rng), config.width);
+        let mut next_queue = VecDeque::new();
+        fill_queue(
+            &mut next_queue,
+            config.preview_count,
+            &mut randomizer,
+            pieces,
+            &mut rng,
+        );
+        let mut piece_counts = vec![0; PIECES.len()];
+        piece_counts[current_piece.piece_type.index()] += 1;
+
+        let mut game = Game {
+            board: Board::new(config.width, config.height + BUFFER_ROWS),
+            config,
+            state: GameState::Countdown(Duration::ZERO),
+            current_piece,
+            next_queue,
+            held: VecDeque::new(),
+            piece_counts,
+            can_hold: true,
+            randomizer,
+            seed,
+            rng,
+            gravity_accumulator: Duration::ZERO,
+            lock_timer: None,
+            lock_resets: 0,
+            clearing: None,
+            game_over: false,
+            won: false,
+            clock: Duration::ZERO,
+            unsimulated: Duration::ZERO,
+            paused: false,
+            score: 0,
+            high_score: 0,
+            drop_points: 0,
+            back_to_back: false,
+            combo: -1,
+            max_combo: 0,
+            last_move_was_rotation: false,
+            last_t_spin: None,
+            last_perfect_clear: None,
+            last_level_up: None,
+            last_hold: None,
+            last_hard_drop_press: None,
+            last_hard_drop: None,
+            lines_sent: 0,
+            piece_inputs: 0,
+            finesse_faults: 0,
+            rise_level: 0,
+            rise_timer: Duration::ZERO,
+            rises: 0,
+            events: Vec::new(),
+            undo_history: VecDeque::new(),
+            level: start_level,
+            lines: 0,
+            pieces_placed: 0,
+            score_log: VecDeque::new(),
+        };
+        if game.config.mode == GameMode::Dig {
+            game.fill_garbage_rows(game.config.garbage_rows);
+        }
+        game
+    }
+
+    /// Starts a fresh game: empty board, zero score, new pieces. The high score
+    /// carries over.
+    pub fn reset(&mut self) {
+        let high_score = self.high_score;
+        *self = Game::with_config(self.config.clone());
+        self.high_score = high_score;
+    }
+
+    fn clear_lines(&mut self) {
+        // Corners must be checked before rows shift
+        let t_spin = self.detect_t_spin();
+        let mut lines_cleared = self.board.clear_full_rows() as u32;
+        if self.config.gravity == GravityMode::Cascade && lines_cleared > 0 {
+            // Settling blocks can complete further rows, which count towards this lock
+            while self.board.apply_cascade_gravity() {
+                let more = self.board.clear_full_rows() as u32;
+                if more == 0 {
+                    break;
+                }
+                lines_cleared += more;
+            }
+        }
+
+        // What happened is reported before the points it earned
+        if let Some(kind) = t_spin {
+            self.last_t_spin = Some((kind, Instant::now()));
+            self.events.push(GameEvent::TSpin(kind));
+        }
+        if lines_cleared > 0 {
+            self.events.push(GameEvent::LinesCleared(lines_cleared));
+        }
+
+        let points = match (t_spin, lines_cleared) {
+            (Some(TSpin::Full), 0) => 400,
+            (Some(TSpin::Full), 1) => 800,
+            (Some(TSpin::Full), 2) => 1200,
+            (Some(TSpin::Full), _) => 1600,
+            (Some(TSpin::Mini), 0) => 100,
+            (Some(TSpin::Mini), 1) => 200,
+            (Some(TSpin::Mini), _) => 400,
+            (None, 1) => 100,
+            (None, 2) => 300,
+            (None, 3) => 500,
+            (None, 0) => 0,
+            (None, _) => 800, // Cascades can chain past four lines
+        };
+
+        // Tetrises and T-spin clears are "difficult" and worth 1.5x while the chain
+        // continues. Only line clears affect the chain; locking without one keeps it alive.
+        let difficult = lines_cleared >= 4 || (t_spin.is_some() && lines_cleared > 0);
+        let back_to_back_bonus = difficult && self.back_to_back;
+        let reason = ScoreReason::Clear {
+            lines: lines_cleared,
+            t_spin,
+            back_to_back: back_to_back_bonus,
+        };
+        if back_to_back_bonus {
+            self.add_score(reason, points * 3 / 2);
+        } else {
+            self.add_score(reason, points);
+        }
+        self.lines_sent = garbage_lines(t_spin, lines_cleared) + back_to_back_bonus as u32;
+        if self.lines_sent > 0 {
+            self.events.push(GameEvent::GarbageSent(self.lines_sent));
+        }
+        if lines_cleared > 0 {
+            self.back_to_back = difficult;
+        }
+
+        // Consecutive clearing locks build a combo worth 50 points per step per level
+        if lines_cleared > 0 {
+            self.combo += 1;
+            if self.combo > 0 {
+                self.max_combo = self.max_combo.max(self.combo as u32);
+                let combo = self.combo as u32;
+                self.add_score(ScoreReason::Combo(combo), combo * 50 * (self.level + 1));
+            }
+        } else {
+            self.combo = -1;
+        }
+
+        if lines_cleared > 0 && self.board.is_empty() {
+            self.last_perfect_clear = Some(Instant::now());
+            self.events.push(GameEvent::PerfectClear);
+            self.add_score(ScoreReason::PerfectClear, PERFECT_CLEAR_BONUS);
+        }
+
+        self.lines += lines_cleared;
+        let level = (self.lines / LINES_PER_LEVEL).max(self.config.start_level);
+        if level > self.level {
+            self.events.push(GameEvent::LevelUp(level));
+            self.last_level_up = Some(Instant::now());
+        }
+        self.level = level;
+
+        if self
+            .config
+            .line_goal()
+            .is_some_and(|goal| self.lines >= goal)
+        {
+            self.end_game(true);
+        }
+        if self.config.mode == GameMode::Dig && lines_cleared > 0 && self.garbage_left() == 0 {
+            self.end_game(true);
+        }
+    }
+
+    /// How many garbage lines the most recent lock sends, for versus play
+    pub fn lines_sent(&self) -> u32 {
+        self.lines_sent
+    }
+
+    /// Pushes `lines` garbage rows up from the bottom, each full except for
+    /// `gap_column`. The falling piece is nudged up out of the way if it can be;
+    /// the game tops out if the stack or the piece is pushed off the board.
+    pub fn add_garbage(&mut self, lines: usize, gap_column: usize) {
+        if self.is_over() {
+            return;
+        }
+
+        let overflowed = self.board.push_garbage_rows(lines, gap_column);
+        while !self.is_valid_position(
+            &self.current_piece.shape,
+            self.current_piece.x,
+            self.current_piece.y,
+        ) && self.current_piece.y > 0
+        {
+            self.current_piece.y -= 1;
+        }
+
+        if overflowed
+            || !self.is_valid_position(
+                &self.current_piece.shape,
+                self.current_piece.x,
+                self.current_piece.y,
+            )
+        {
+            self.top_out();
+        }
+    }
+
+    /// Pushes `rows` garbage rows up from the bottom one at a time, each with its gap
+    /// in a random column, as the starting stack of a dig game
+    pub fn fill_garbage_rows(&mut self, rows: usize) {
+        use rand::Rng;
+
+        for _ in 0..rows {
+            let gap_column = self.rng.gen_range(0..self.board.width());
+            self.add_garbage(1, gap_column);
+        }
+    }
+
+    /// Time between garbage rows rising at the current rise level of a survival game
+    pub fn rise_interval(&self) -> Duration {
+        SURVIVAL_RISE_INTERVAL
+            .mul_f64(0.85_f64.powi(self.rise_level as i32))
+            .max(SURVIVAL_MIN_RISE_INTERVAL)
+    }
+
+    /// How long until the next garbage row rises, in a survival game
+    pub fn time_to_next_rise(&self) -> Option<Duration> {
+        (self.config.mode == GameMode::Survival)
+            .then(|| self.rise_interval().saturating_sub(self.rise_timer))
+    }
+
+    /// Counts `dt` towards the next garbage row in a survival game and pushes it up
+    /// once due. Rows wait for a clear to finish, since it works on the rows as
+    /// they stood when it started.
+    fn advance_rise(&mut self, dt: Duration) {
+        use rand::Rng;
+
+        if self.config.mode != GameMode::Survival {
+            return;
+        }
+        self.rise_timer = (self.rise_timer + dt).min(self.rise_interval());
+        if self.rise_timer < self.rise_interval() || self.clearing.is_some() {
+            return;
+        }
+        self.rise_timer = Duration::ZERO;
+        let gap_column = self.rng.gen_range(0..self.board.width());
+        self.add_garbage(1, gap_column);
+        if self.is_over() {
+            return;
+        }
+        self.rises += 1;
+        self.add_score(
+            ScoreReason::Survived,
+            SURVIVAL_RISE_POINTS * (self.rise_level + 1),
+        );
+        self.rise_level = self.rises / SURVIVAL_RISES_PER_LEVEL;
+    }
+
+    /// Rows still holding garbage, which a dig game is won by clearing
+    pub fn garbage_left(&self) -> usize {
+        self.board
+            .rows()
+            .iter()
+            .filter(|row| row.contains(&Cell::Garbage))
+            .count()
+    }
+
+    /// Classifies the lock of a T piece straight after a rotation using the 3-corner
+    /// rule: three occupied diagonal corners make a T-spin, which is a mini unless
+    /// both corners on the side the T points towards are occupied.
+    fn detect_t_spin(&self) -> Option<TSpin> {
+        let piece = &self.current_piece;
+        if piece.piece_type != TetrominoType::T || !self.last_move_was_rotation {
+            return None;
+        }
+
+        // Walls and floor count as occupied
+        let occupied = |dx: i32, dy: i32| {
+            let x = piece.x + dx;
+            let y = piece.y + dy;
+            if x < 0 || x >= self.board.width() as i32 || y >= self.board.height() as i32 {
+                return true;
+            }
+            y >= 0 && self.board.is_filled(x as usize, y as usize)
+        };
+
+        // Corners of the 3x3 box around the T's center, front pair first
+        let corners = match piece.rotation {
+            0 => [(0, 0), (2, 0), (0, 2), (2, 2)],
+            1 => [(2, 0), (2, 2), (0, 0), (0, 2)],
+            2 => [(0, 2), (2, 2), (0, 0), (2, 0)],
+            _ => [(0, 0), (0, 2), (2, 0), (2, 2)],
+        };
+        let filled: Vec<bool> = corners.iter().map(|&(dx, dy)| occupied(dx, dy)).collect();
+
+        if filled.iter().filter(|&&is_filled| is_filled).count() < 3 {
+            None
+        } else if filled[0] && filled[1] {
+            Some(TSpin::Full)
+        } else {
+            Some(TSpin::Mini)
+        }
+    }
+
+    /// Awards `points` and notes them in the score log. Consecutive soft drop points
+    /// for the same piece share one entry.
+    fn add_score(&mut self, reason: ScoreReason, points: u32) {
+        if points == 0 {
+            return;
+        }
+        self.score += points;
+        if self.config.mode != GameMode::Zen {
+            self.high_score = self.high_score.max(self.score);
+        }
+
+        // Drops score before their piece locks, clears after it
+        let piece = match reason {
+            ScoreReason::SoftDrop | ScoreReason::HardDrop => self.pieces_placed + 1,
+            _ => self.pieces_placed,
+        };
+        let entry = ScoreEntry {
+            piece,
+            reason,
+            points,
+        };
+        self.events.push(GameEvent::Scored(entry));
+        match self.score_log.back_mut() {
+            Some(last)
+                if reason == ScoreReason::SoftDrop
+                    && last.reason == reason
+                    && last.piece == piece =>
+            {
+                last.points += points;
+            }
+            _ => {
+                if self.score_log.len() == SCORE_LOG_LEN {
+                    self.score_log.pop_front();
+                }
+                self.score_log.push_back(entry);
+            }
+        }
+    }
+
+    /// Gravity interval for the current level, following the configured table
+    pub fn tick_rate(&self) -> Duration {
+        self.config.gravity_table.tick_rate(self.level)
+    }
+
+    /// Lines left to clear before the level goes up. Starting above level 0 counts
+    /// from the lines that level would normally take.
+    pub fn lines_to_next_level(&self) -> u32 {
+        ((self.level + 1) * LINES_PER_LEVEL).saturating_sub(self.lines)
+    }
+
+    /// Applies a player action. Pause and restart always work; everything else is
+    /// ignored while paused, counting down, after game over, or during a line-clear
+    /// animation.
+    /// Saving, quitting and the debug overlay are left to the caller. Returns what the
+    /// action set off.
+    pub fn handle_action(&mut self, action: Action) -> Vec<GameEvent> {
+        match action {
+            Action::Pause => self.toggle_pause(),
+            Action::Restart => self.reset(),
+            Action::Save
+            | Action::Quit
+            | Action::ToggleDebug
+            | Action::ToggleGrid
+            | Action::ToggleMinimal
+            | Action::ToggleHelp
+            | Action::ToggleMetrics
+            | Action::ToggleMirror => {}
+            _ if self.paused
+                || self.is_counting_down()
+                || self.is_over()
+                || self.clearing.is_some() => {}
+            Action::MoveLeft => {
+                if self.move_piece(-1, 0) {
+                    self.piece_inputs += 1;
+                }
+            }
+            Action::MoveRight => {
+                if self.move_piece(1, 0) {
+                    self.piece_inputs += 1;
+                }
+            }
+            Action::RepeatLeft => {
+                self.move_piece(-1, 0);
+            }
+            Action::RepeatRight => {
+                self.move_piece(1, 0);
+            }
+            Action::SoftDrop => {
+                self.soft_drop();
+            }
+            Action::HardDrop => {
+                if self.hard_drop_confirmed() {
+                    return self.hard_drop();
+                }
+                self.soft_drop();
+            }
+            Action::RotateCW | Action::RotateCCW | Action::Rotate180 => {
+                let events = match action {
+                    Action::RotateCW => self.rotate_piece(),
+                    Action::RotateCCW => self.rotate_piece_ccw(),
+                    _ => self.rotate_piece_180(),
+                };
+                if events.contains(&GameEvent::Rotated) {
+                    self.piece_inputs += 1;
+                }
+                return events;
+            }
+            Action::Hold => self.hold(),
+            Action::Undo => {
+                self.undo();
+            }
+        }
+        self.take_events()
+    }
+
+    /// Whether a hard drop press goes through: always, unless confirming is on and
+    /// this isn't the second press within the window, in which case it's noted as
+    /// the first
+    fn hard_drop_confirmed(&mut self) -> bool {
+        let Some(window) = self.config.confirm_hard_drop else {
+            return true;
+        };
+        if self
+            .last_hard_drop_press
+            .is_some_and(|since| since <= window)
+        {
+            self.last_hard_drop_press = None;
+            true
+        } else {
+            self.last_hard_drop_press = Some(Duration::ZERO);
+            false
+        }
+    }
+
+    /// Hands over the events gathered since they were last returned
+    fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Writes the full game state to `path` as JSON
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// Restores a game written by `save_to`
+    pub fn load_from(path: &Path) -> io::Result<Game> {
+        let mut game: Game = serde_json::from_str(&fs::read_to_string(path)?)?;
+        // A moment to get ready again, unless it's waiting paused anyway
+        if !game.paused {
+            game.state = GameState::Countdown(Duration::ZERO);
+        }
+        Ok(game)
+    }
+
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            board: self
+                .board
+                .rows()
+                .iter()
+                .map(|row| row.iter().map(|cell| *cell != Cell::Empty).collect())
+                .collect(),
+            piece_type: self.current_piece.piece_type,
+            piece_x: self.current_piece.x,
+            piece_y: self.current_piece.y,
+            rotation: self.current_piece.rotation,
+            shape: self.current_piece.shape,
+            next: self.next_queue.iter().copied().collect(),
+            held: self.held.iter().copied().collect(),
+            score: self.score,
+            lines: self.lines,
+            level: self.level,
+            game_over: self.is_over(),
+        }
+    }
+
+    /// Takes on the state in `snapshot`, taken from someone else's game, so the usual
+    /// drawing shows it to a spectator. The snapshot doesn't say which piece filled
+    /// each cell, so the stack is shown as garbage.
+    pub fn show_snapshot(&mut self, snapshot: &GameSnapshot) {
+        let cells = snapshot
+            .board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&filled| if filled { Cell::Garbage } else { Cell::Empty })
+                    .collect()
+            })
+            .collect();
+        self.board = Board::from_cells(cells);
+        self.config.width = self.board.width();
+        self.config.height = self.board.height().saturating_sub(BUFFER_ROWS);
+        self.config.preview_count = snapshot.next.len();
+        self.current_piece = Tetromino {
+            piece_type: snapshot.piece_type,
+            shape: snapshot.shape,
+            x: snapshot.piece_x,
+            y: snapshot.piece_y,
+            rotation: snapshot.rotation,
+        };
+        self.next_queue = snapshot.next.iter().copied().collect();
+        self.held = snapshot.held.iter().copied().collect();
+        self.config.hold_capacity = snapshot.held.len().max(1);
+        self.score = snapshot.score;
+        self.lines = snapshot.lines;
+        self.level = snapshot.level;
+        self.game_over = snapshot.game_over;
+        self.state = GameState::Playing;
+    }
+
+    /// Fingerprint of the board, score, level, lines and pieces dealt. The same game
+    /// gives the same hash on any machine and build, so a replay can check it ends
+    /// just where it did when recorded.
+    pub fn state_hash(&self) -> u64 {
+        // FNV-1a rather than std's hasher, which may change between Rust releases
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        feed(&(self.board.width() as u32).to_le_bytes());
+        feed(&(self.board.height() as u32).to_le_bytes());
+        for cell in self.board.rows().iter().flatten() {
+            feed(&[match cell {
+                Cell::Empty => 0,
+                Cell::Filled(piece) => piece.0 + 1,
+                Cell::Garbage => u8::MAX,
+            }]);
+        }
+        for value in [self.score, self.level, self.lines] {
+            feed(&value.to_le_bytes());
+        }
+        for count in &self.piece_counts {
+            feed(&count.to_le_bytes());
+        }
+        hash
+    }
+
+    /// Ends the game as a win, for when the opponent tops out in versus play
+    pub fn win(&mut self) {
+        if !self.is_over() {
+            self.end_game(true);
+        }
+    }
+
+    /// True once the game has ended, whether by topping out or reaching the goal
+    pub fn is_over(&self) -> bool {
+        self.game_over || self.won
+    }
+
+    pub fn is_counting_down(&self) -> bool {
+        matches!(self.state, GameState::Countdown(_))
+    }
+
+    /// Time left before play starts, `None` once playing
+    pub fn countdown_remaining(&self) -> Option<Duration> {
+        match self.state {
+            GameState::Countdown(counted) => Some(COUNTDOWN.saturating_sub(counted)),
+            GameState::Playing | GameState::GameOverAnimation(_) => None,
+        }
+    }
+
+    /// Ends the game as a loss, except in zen mode where the top of the stack is
+    /// cleared to make room and play carries on
+    fn top_out(&mut self) {
+        if self.config.mode == GameMode::Zen {
+            self.board.clear_top_rows(ZEN_CLEARED_ROWS);
+        } else {
+            self.end_game(false);
+        }
+    }
+
+    fn end_game(&mut self, won: bool) {
+        if won {
+            self.won = true;
+        } else {
+            self.game_over = true;
+            self.events.push(GameEvent::GameOver);
+            if self.config.top_out_animation {
+                self.state = GameState::GameOverAnimation(Instant::now());
+            }
+        }
+    }
+
+    pub fn is_topping_out(&self) -> bool {
+        matches!(self.state, GameState::GameOverAnimation(_))
+    }
+
+    /// Jumps to the end of the top-out animation, straight to the game-over screen
+    pub fn skip_top_out(&mut self) {
+        if self.is_topping_out() {
+            self.fill_rows_from_bottom(self.board.height());
+            self.state = GameState::Playing;
+        }
+    }
+
+    /// Fills the rows the top-out animation has reached by now
+    fn advance_top_out(&mut self, started: Instant) {
+        let rows = self.config.height.min(self.board.height());
+        let progress = started.elapsed().as_secs_f64() / TOP_OUT_DURATION.as_secs_f64();
+        // The bottom row goes straight away, the top one by the end
+        self.fill_rows_from_bottom((progress * rows as f64) as usize + 1);
+        if progress >= 1.0 {
+            self.skip_top_out();
+        }
+    }
+
+    fn fill_rows_from_bottom(&mut self, count: usize) {
+        let rows = self.config.height.min(self.board.height());
+        let height = self.board.height();
+        for y in height - count.min(rows)..height {
+            self.board.fill_row_with_garbage(y);
+        }
+    }
+
+    /// Time played so far, excluding pauses and frozen once the game ends
+    pub fn elapsed(&self) -> Duration {
+        self.clock
+    }
+
+    /// Average pieces locked per second of play
+    pub fn pieces_per_second(&self) -> f64 {
+        per_second(self.pieces_placed, self.elapsed())
+    }
+
+    /// Average lines cleared per minute of play
+    pub fn lines_per_minute(&self) -> f64 {
+        per_second(self.lines, self.elapsed()) * 60.0
+    }
+
+    /// Pauses or resumes. Resuming counts down again before play continues, and a
+    /// countdown interrupted by pausing starts over.
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.state = if self.paused {
+            GameState::Playing
+        } else {
+            GameState::Countdown(Duration::ZERO)
+        };
+    }
+
+    /// Advances the simulation by `dt` of real time, as many whole `step`s as fit,
+    /// carrying the rest over to the next call. Returns the events that happened
+    /// meanwhile.
+    pub fn update(&mut self, dt: Duration) -> Vec<GameEvent> {
+        self.unsimulated += dt;
+        let mut events = Vec::new();
+        while self.unsimulated >= SIMULATION_STEP {
+            self.unsimulated -= SIMULATION_STEP;
+            events.extend(self.step());
+        }
+        events
+    }
+
+    /// Advances the simulation by one `SIMULATION_STEP`, running a gravity `tick`
+    /// each time a tick interval has built up. Time doesn't accumulate while paused,
+    /// so resuming never drops the piece early. Returns the events it set off.
+    pub fn step(&mut self) -> Vec<GameEvent> {
+        if self.is_topping_out() {
+            return self.tick();
+        }
+        if self.is_over() || self.paused {
+            return Vec::new();
+        }
+        if let GameState::Countdown(counted) = self.state {
+            if counted < COUNTDOWN {
+                self.state = GameState::Countdown(counted + SIMULATION_STEP);
+                return Vec::new();
+            }
+            self.state = GameState::Playing;
+        }
+
+        self.clock += SIMULATION_STEP;
+        for timer in [
+            self.lock_timer.as_mut(),
+            self.clearing.as_mut().map(|(_, timer)| timer),
+            self.last_hard_drop_press.as_mut(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *timer += SIMULATION_STEP;
+        }
+
+        // Line-clear animations and lock delay run on their own timers, so check them every step
+        let lock_expired = self
+            .lock_timer
+            .is_some_and(|rested| rested >= self.config.lock_delay);
+        let mut events = Vec::new();
+        if self.clearing.is_some() || lock_expired {
+            events.extend(self.tick());
+        }
+
+        self.gravity_accumulator += SIMULATION_STEP;
+        while self.gravity_accumulator >= self.tick_rate() && !self.is_over() {
+            self.gravity_accumulator -= self.tick_rate();
+            events.extend(self.tick());
+        }
+        self.advance_rise(SIMULATION_STEP);
+        events.extend(self.take_events());
+        events
+    }
+
+    /// One step of gravity, or of the line-clear animation while one runs. Returns
+    /// what it set off, such as a lock and the lines it cleared.
+    pub fn tick(&mut self) -> Vec<GameEvent> {
+        if let GameState::GameOverAnimation(started) = self.state {
+            self.advance_top_out(started);
+            return self.take_events();
+        }
+        if self.is_over() || self.paused {
+            return Vec::new();
+        }
+
+        // The next piece only spawns once the flash animation has finished
+        if let Some((_, flashed)) = &self.clearing {
+            if *flashed >= LINE_CLEAR_DELAY {
+                self.clearing = None;
+                self.clear_lines();
+                self.spawn_new_piece();
+            }
+            return self.take_events();
+        }
+
+        if self.move_piece(0, 1) {
+            self.lock_timer = None;
+            return self.take_events();
+        }
+
+        // Grounded: give the player the lock delay to slide or rotate before locking
+        match self.lock_timer {
+            None => self.lock_timer = Some(Duration::ZERO),
+            Some(rested) if rested >= self.config.lock_delay => {
+                self.remember_for_undo();
+                self.lock_piece();
+            }
+            Some(_) => {}
+        }
+        self.take_events()
+    }
+
+    fn lock_piece(&mut self) {
+        if self.config.finesse {
+            self.judge_finesse();
+        }
+        self.freeze_piece();
+        self.events.push(GameEvent::PieceLocked);
+
+        // Lock out: a piece that settles without reaching the visible field tops out
+        if self
+            .current_piece_cells()
+            .all(|(_, y)| y < BUFFER_ROWS as i32)
+        {
+            self.top_out();
+            if self.is_over() {
+                return;
+            }
+        }
+
+        // Full rows flash before being removed; `tick` finishes the clear
+        let full_rows = self.board.full_rows();
+        if full_rows.is_empty() {
+            self.clear_lines();
+            self.spawn_new_piece();
+        } else {
+            self.clearing = Some((full_rows, Duration::ZERO));
+        }
+    }
+
+    /// Keeps the game as it is, just before a lock, for `undo` to go back to. Only zen
+    /// games allow undoing, so nothing is kept otherwise.
+    fn remember_for_undo(&mut self) {
+        if self.config.mode != GameMode::Zen {
+            return;
+        }
+        let history = std::mem::take(&mut self.undo_history);
+        let before = self.clone();
+        self.undo_history = history;
+        self.undo_history.push_back(before);
+        if self.undo_history.len() > UNDO_LIMIT {
+            self.undo_history.pop_front();
+        }
+    }
+
+    /// Takes back the last lock, putting the piece back where it was before it
+    /// dropped or locked. The game clock and high score carry on regardless.
+    /// Returns whether there was anything to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(mut before) = self.undo_history.pop_back() else {
+            return false;
+        };
+        before.undo_history = std::mem::take(&mut self.undo_history);
+        before.clock = self.clock;
+        before.unsimulated = self.unsimulated;
+        before.high_score = self.high_score;
+        // Its lock delay would have run out long ago, and the piece should get a
+        // full one again
+        before.lock_timer = None;
+        before.gravity_accumulator = Duration::ZERO;
+        *self = before;
+        true
+    }
+
+    /// Counts a finesse fault if the piece took more inputs than it needed. Only
+    /// pieces that could have dropped straight down from the top are judged, since
+    /// tucks and spins under overhangs take extra inputs on purpose.
+    fn judge_finesse(&mut self) {
+        let piece = &self.current_piece;
+        let from_above = (0..piece.y).all(|y| self.is_valid_position(&piece.shape, piece.x, y));
+        if !from_above {
+            return;
+        }
+        let fewest = finesse_minimum(piece, self.board.width());
+        if fewest.is_some_and(|fewest| self.piece_inputs > fewest) {
+            self.finesse_faults += 1;
+        }
+    }
+
+    /// Restarts the lock delay after a successful move or rotation while grounded,
+    /// up to `max_lock_resets` times per piece to prevent infinite stalling
+    fn reset_lock_timer(&mut self) {
+        if self.lock_timer.is_some() && self.lock_resets < self.config.max_lock_resets {
+            self.lock_timer = Some(Duration::ZERO);
+            self.lock_resets += 1;
+        }
+    }
+
+    /// Drops and locks the piece at once, returning `HardDropped` followed by
+    /// everything the lock set off
+    pub fn hard_drop(&mut self) -> Vec<GameEvent> {
+        if self.is_over() || self.clearing.is_some() {
+            return Vec::new();
+        }
+        self.remember_for_undo();
+
+        let (left, right, from_y) = self.current_piece_cells().fold(
+            (i32::MAX, i32::MIN, i32::MAX),
+            |(left, right, top), (x, y)| (left.min(x), right.max(x), top.min(y)),
+        );
+
+        // Drop until blocked, awarding 2 points per cell fallen
+        let mut cells_dropped = 0;
+        while self.move_piece(0, 1) {
+            cells_dropped += 1;
+        }
+        self.events.push(GameEvent::HardDropped);
+        self.add_score(ScoreReason::HardDrop, cells_dropped * 2);
+        self.drop_points += cells_dropped * 2;
+        if cells_dropped > 0 {
+            let trail = DropTrail {
+                left,
+                right,
+                from_y,
+                to_y: from_y + cells_dropped as i32,
+            };
+            self.last_hard_drop = Some((trail, Instant::now()));
+        }
+
+        self.lock_piece();
+        self.take_events()
+    }
+
+    /// Player-initiated downward move, worth 1 point per cell unlike gravity ticks
+    pub fn soft_drop(&mut self) -> bool {
+        if self.is_over() || self.clearing.is_some() {
+            return false;
+        }
+
+        let moved = self.move_piece(0, 1);
+        if moved {
+            self.add_score(ScoreReason::SoftDrop, 1);
+            self.drop_points += 1;
+        }
+        moved
+    }
+
+    /// How much of its lock delay the grounded piece has used up, from 0 as it lands
+    /// to 1 as it locks. `None` while the piece is falling or nothing is in play.
+    pub fn lock_progress(&self) -> Option<f32> {
+        if self.is_over() || self.clearing.is_some() {
+            return None;
+        }
+        let rested = self.lock_timer?;
+        let delay = self.config.lock_delay.as_secs_f32();
+        if delay == 0.0 {
+            return Some(1.0);
+        }
+        Some((rested.as_secs_f32() / delay).min(1.0))
+    }
+
+    /// How far the falling piece is towards its next gravity step, from 0 just after
+    /// one to nearly 1 right before the next, for drawing it in between rows. Always 0
+    /// while the piece can't fall, so it's never drawn below where it can go.
+    pub fn fall_progress(&self) -> f32 {
+        let piece = &self.current_piece;
+        let falling = self.state == GameState::Playing
+            && !self.paused
+            && !self.is_over()
+            && self.clearing.is_none()
+            && self.is_valid_position(&piece.shape, piece.x, piece.y + 1);
+        if !falling {
+            return 0.0;
+        }
+        let progress = self.gravity_accumulator.as_secs_f32() / self.tick_rate().as_secs_f32();
+        progress.clamp(0.0, 1.0 - f32::EPSILON)
+    }
+
+    /// Position where the current piece would land if dropped straight down
+    pub fn ghost_position(&self) -> (i32, i32) {
+        let x = self.current_piece.x;
+        let mut y = self.current_piece.y;
+        while self.is_valid_position(&self.current_piece.shape, x, y + 1) {
+            y += 1;
+        }
+        (x, y)
+    }
+
+    pub fn move_piece(&mut self, dx: i32, dy: i32) -> bool {
+        let new_x = self.current_piece.x + dx;
+        let new_y = self.current_piece.y + dy;
+
+        if self.is_valid_position(&self.current_piece.shape, new_x, new_y) {
+            self.current_piece.x = new_x;
+            self.current_piece.y = new_y;
+            self.last_move_was_rotation = false;
+            self.reset_lock_timer();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Steps the current piece sideways until its leftmost filled column sits in
+    /// board column `x`, stopping early if something is in the way. Each step counts
+    /// as an input towards finesse, the same as a key press.
+    pub fn move_to_column(&mut self, x: i32) {
+        if self.paused || self.is_counting_down() || self.is_over() || self.clearing.is_some() {
+            return;
+        }
+
+        let left_offset = self
+            .current_piece
+            .shape
+            .cells()
+            .map(|(col, _)| col)
+            .min()
+            .unwrap_or(0) as i32;
+        loop {
+            let dx = (x - (self.current_piece.x + left_offset)).signum();
+            if dx == 0 || !self.move_piece(dx, 0) {
+                break;
+            }
+            self.piece_inputs += 1;
+        }
+    }
+
+    /// Board coordinates of the current piece's filled cells
+    fn current_piece_cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let piece = &self.current_piece;
+        piece
+            .shape
+            .cells()
+            .map(move |(col, row)| (piece.x + col as i32, piece.y + row as i32))
+    }
+
+    fn is_valid_position(&self, shape: &Shape, x: i32, y: i32) -> bool {
+        // The buffer rows give pieces room above the visible field, so nothing may
+        // poke out past the top of the board itself
+        !self.board.collides(shape, x, y)
+    }
+
+    fn freeze_piece(&mut self) {
+        let piece_type = self.current_piece.piece_type;
+        let cells: Vec<_> = self.current_piece_cells().collect();
+        for (board_x, board_y) in cells {
+            if board_y >= 0 && board_y < self.board.height() as i32 {
+                self.board
+                    .set(board_x as usize, board_y as usize, piece_type);
+            }
+        }
+        self.pieces_placed += 1;
+    }
+
+    /// Stashes the current piece, swapping in the held one (or the next piece if
+    /// nothing is held yet). Only allowed once per locked piece.
+    pub fn hold(&mut self) {
+        if self.is_over() || self.clearing.is_some() || !self.can_hold {
+            return;
+        }
+
+        self.held.push_back(self.current_piece.piece_type);
+        let released = if self.held.len() > self.config.hold_capacity.max(1) {
+            self.held.pop_front()
+        } else {
+            None
+        };
+        match released {
+            Some(held_type) => {
+                self.current_piece = Tetromino::new(held_type, self.board.width());
+                self.lock_timer = None;
+                self.lock_resets = 0;
+                self.piece_inputs = 0;
+                if !self.is_valid_position(
+                    &self.current_piece.shape,
+                    self.current_piece.x,
+                    self.current_piece.y,
+                ) {
+                    self.top_out();
+                }
+            }
+            None => self.spawn_new_piece(),
+        }
+        self.can_hold = false;
+        self.last_hold = Some(Instant::now());
+    }
+
+    fn spawn_new_piece(&mut self) {
+        self.lock_timer = None;
+        self.lock_resets = 0;
+        self.piece_inputs = 0;
+        // Every fresh piece may be held once
+        self.can_hold = true;
+
+        let next_type = self.next_queue.pop_front().unwrap();
+        fill_queue(
+            &mut self.next_queue,
+            self.config.preview_count,
+            &mut self.randomizer,
+            self.config.piece_set.pieces(),
+            &mut self.rng,
+        );
+        self.current_piece = Tetromino::new(next_type, self.board.width());
+        self.piece_counts[self.current_piece.piece_type.index()] += 1;
+
+        // Check if the new piece can be placed at spawn position
+        if !self.is_valid_position(
+            &self.current_piece.shape,
+            self.current_piece.x,
+            self.current_piece.y,
+        ) {
+            self.top_out();
+        }
+    }
+}
+
+/// `count` spread over `elapsed`, or 0 before any time has passed
+fn per_second(count: u32, elapsed: Duration) -> f64 {
+    let seconds = elapsed.as_secs_f64();
+    if seconds > 0.0 {
+        count as f64 / seconds
+    } else {
+        0.0
+    }
+}
+
+/// Garbage sent by a clear before any back-to-back bonus, following the guideline
+/// attack table
+fn garbage_lines(t_spin: Option<TSpin>, lines_cleared: u32) -> u32 {
+    match (t_spin, lines_cleared) {
+        (_, 0) => 0,
+        (Some(TSpin::Full), lines) => lines * 2,
+        (Some(TSpin::Mini), lines) => lines - 1,
+        (None, lines) if lines >= 4 => 4,
+        (None, lines) => lines - 1,
+    }
+}
+
+/// SRS kick offsets for J, L, S, T and Z, as (dx, dy) with y pointing up
+const JLSTZ_KICKS: [[(i32, i32); 5]; 8] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 0 -> R
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 0 -> L
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 2
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 0
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 2 -> L
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 2 -> R
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 0
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 2
+];
+
+/// SRS kick offsets for the I piece, as (dx, dy) with y pointing up
+const I_KICKS: [[(i32, i32); 5]; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // 0 -> R
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // 0 -> L
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // R -> 2
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // R -> 0
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // 2 -> L
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // 2 -> R
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // L -> 0
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // L -> 2
+];
+
+/// Fallbacks for a 180 degree turn, which SRS doesn't define: in place, then up, then sideways
+const ROTATE_180_KICKS: [(i32, i32); 4] = [(0, 0), (0, 1), (1, 0), (-1, 0)];
+
+/// Candidate offsets to try, in order, when rotating from one state to another
+fn srs_kicks(piece_type: TetrominoType, from: u8, to: u8) -> &'static [(i32, i32)] {
+    if to == (from + 2) % 4 {
+        return &ROTATE_180_KICKS;
+    }
+
+    // Each state has a clockwise entry followed by a counter-clockwise one
+    let clockwise = to == (from + 1) % 4;
+    let index = from as usize * 2 + if clockwise { 0 } else { 1 };
+
+    // Pieces share the table of the tetromino with their box size, so the
+    // pentominoes in a 4×4 or 5×5 box kick like the I
+    match piece_type.shape().size() {
+        0..=2 => &[(0, 0)],
+        3 => &JLSTZ_KICKS[index],
+        _ => &I_KICKS[index],
+    }
+}
+
+/// Fewest moves and rotations, wall kicks included, that take a freshly spawned piece
+/// of the same type to the columns and orientation `target` covers, on an empty
+/// board `width` wide. Orientations that cover the same cells, like the two flat
+/// ones of an S, count as the same target. Holding a direction to the wall counts
+/// as a single move. `None` if it can't get there at all.
+fn finesse_minimum(target: &Tetromino, width: usize) -> Option<u32> {
+    // The cells of a piece at `x`, with its top row as row 0, so where it sits
+    // vertically doesn't matter
+    let footprint = |shape: &Shape, x: i32| {
+        let top = shape.cells().map(|(_, row)| row).min().unwrap_or(0);
+        let mut cells: Vec<_> = shape
+            .cells()
+            .map(|(col, row)| (x + col as i32, row - top))
+            .collect();
+        cells.sort();
+        cells
+    };
+    let fits = |shape: &Shape, x: i32| {
+        shape
+            .cells()
+            .all(|(col, _)| (0..width as i32).contains(&(x + col as i32)))
+    };
+    let goal = footprint(&target.shape, target.x);
+
+    let spawn = Tetromino::new(target.piece_type, width);
+    let mut seen = vec![(spawn.x, spawn.rotation)];
+    let mut queue = VecDeque::from([(spawn.shape, spawn.x, spawn.rotation, 0)]);
+    while let Some((shape, x, rotation, inputs)) = queue.pop_front() {
+        if footprint(&shape, x) == goal {
+            return Some(inputs);
+        }
+        let mut next = vec![(shape, x - 1, rotation), (shape, x + 1, rotation)];
+        // Holding a direction slides the piece to the wall for one press
+        for step in [-1, 1] {
+            let mut wall = x;
+            while fits(&shape, wall + step) {
+                wall += step;
+            }
+            next.push((shape, wall, rotation));
+        }
+        for (turned, to) in [
+            (shape.rotate_clockwise(), (rotation + 1) % 4),
+            (shape.rotate_counter_clockwise(), (rotation + 3) % 4),
+            (shape.rotate_180(), (rotation + 2) % 4),
+        ] {
+            // With nothing on the board only the walls can push a kick along
+            let kick = srs_kicks(target.piece_type, rotation, to)
+                .iter()
+                .find(|&&(dx, _)| fits(&turned, x + dx));
+            if let Some(&(dx, _)) = kick {
+                next.push((turned, x + dx, to));
+            }
+        }
+        for (shape, x, rotation) in next {
+            if fits(&shape, x) && !seen.contains(&(x, rotation)) {
+                seen.push((x, rotation));
+                queue.push_back((shape, x, rotation, inputs + 1));
+            }
+        }
+    }
+    None
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(Difficulty::Normal)
+    }
+}
+
+/// Where the game in progress is saved, by the save key or on quitting, to be
+/// resumed on the next launch
+pub fn save_path() -> Option<PathBuf> {
+    app_dir().map(|dir| dir.join("save.json"))
+}
+
+/// Tops the queue up from the randomizer until it holds `count` pieces (at least
+/// one, so there is always a piece to spawn)
+fn fill_queue(
+    queue: &mut VecDeque<TetrominoType>,
+    count: usize,
+    randomizer: &mut Randomizer,
+    pieces: &[TetrominoType],
+    rng: &mut ChaCha12Rng,
+) {
+    while queue.len() < count.max(1) {
+        queue.push_back(randomizer.next(pieces, rng));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_game() -> Game {
+        let mut game = Game::with_seed(1);
+        game.board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        game.score = 0;
+        game.state = GameState::Playing;
+        game
+    }
+
+    fn fill_row(game: &mut Game, y: usize) {
+        for x in 0..BOARD_WIDTH {
+            game.board.set(x, y, TetrominoType::Z);
+        }
+    }
+
+    /// Fills a row except for one gap so it never clears
+    fn fill_row_with_gap(game: &mut Game, y: usize, gap: usize) {
+        for x in (0..BOARD_WIDTH).filter(|&x| x != gap) {
+            game.board.set(x, y, TetrominoType::Z);
+        }
+    }
+
+    fn assert_rows_empty(game: &Game, rows: std::ops::Range<usize>) {
+        for y in rows {
+            assert!(
+                game.board.rows()[y].iter().all(|cell| *cell == Cell::Empty),
+                "row {y} should be empty"
+            );
+        }
+    }
+
+    #[test]
+    fn wide_board_centers_spawn_and_moves_wall() {
+        let mut game = Game::with_config(GameConfig {
+            width: 16,
+            height: 10,
+            ..GameConfig::default()
+        });
+        game.current_piece = Tetromino::new(TetrominoType::O, 16);
+        assert_eq!(game.current_piece.x, 7);
+
+        while game.move_piece(1, 0) {}
+
+        assert_eq!(game.current_piece.x, 14);
+    }
+
+    #[test]
+    fn actions_are_ignored_while_paused() {
+        let mut game = empty_game();
+        let x = game.current_piece.x;
+
+        game.handle_action(Action::Pause);
+        game.handle_action(Action::MoveLeft);
+        assert_eq!(game.current_piece.x, x);
+
+        game.handle_action(Action::Pause);
+        game.handle_action(Action::MoveLeft);
+        // Still counting down after resuming
+        assert_eq!(game.current_piece.x, x);
+
+        game.state = GameState::Playing;
+        game.handle_action(Action::MoveLeft);
+        assert_eq!(game.current_piece.x, x - 1);
+    }
+
+    #[test]
+    fn countdown_holds_gravity_until_it_runs_out() {
+        let mut game = empty_game();
+        game.state = GameState::Countdown(Duration::ZERO);
+        let tick_rate = game.tick_rate();
+
+        game.update(tick_rate * 3);
+        assert_eq!(game.current_piece.y, 0);
+        assert!(game.is_counting_down());
+
+        game.state = GameState::Countdown(COUNTDOWN);
+        game.update(tick_rate);
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.current_piece.y, 1);
+        // The countdown doesn't count as play time
+        assert!(game.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn resuming_from_pause_counts_down_again() {
+        let mut game = empty_game();
+
+        game.toggle_pause();
+        assert_eq!(game.state, GameState::Playing);
+        game.toggle_pause();
+
+        assert!(game.is_counting_down());
+        assert!(game.countdown_remaining().unwrap() > COUNTDOWN - Duration::from_secs(1));
+    }
+
+    #[test]
+    fn piece_counts_track_spawns() {
+        let mut game = Game::with_seed(7);
+
+        for _ in 0..6 {
+            game.spawn_new_piece();
+        }
+
+        // The first seven pieces come from a single bag
+        assert_eq!(game.piece_counts[..7], [1; 7]);
+        assert!(game.piece_counts[7..].iter().all(|&count| count == 0));
+    }
+
+    #[test]
+    fn pentomino_set_deals_each_pentomino_once_per_bag() {
+        let mut game = Game::with_config(GameConfig {
+            seed: Some(7),
+            piece_set: PieceSet::Pentominoes,
+            ..GameConfig::default()
+        });
+
+        for _ in 0..11 {
+            game.spawn_new_piece();
+        }
+
+        assert!(game.piece_counts[..7].iter().all(|&count| count == 0));
+        assert_eq!(game.piece_counts[7..], [1; 12]);
+    }
+
+    #[test]
+    fn pieces_are_saved_by_name() {
+        let pentomino = TetrominoType::by_name("X5").unwrap();
+        assert_eq!(serde_json::to_string(&pentomino).unwrap(), "\"X5\"");
+        assert_eq!(
+            serde_json::from_str::<TetrominoType>("\"J\"").unwrap(),
+            TetrominoType::J
+        );
+        assert!(serde_json::from_str::<TetrominoType>("\"Q\"").is_err());
+    }
+
+    #[test]
+    fn every_pentomino_has_five_cells() {
+        for piece_type in TetrominoType::PENTOMINOES {
+            assert_eq!(piece_type.shape().cells().count(), 5, "{piece_type:?}");
+        }
+        assert_eq!(PieceSet::Pentominoes.max_shape_size(), MAX_SHAPE_SIZE);
+    }
+
+    #[test]
+    fn spawning_pops_the_front_of_the_next_queue() {
+        let mut game = Game::with_seed(7);
+        assert_eq!(game.next_queue.len(), 5);
+        let upcoming: Vec<_> = game.next_queue.iter().copied().collect();
+
+        for &expected in &upcoming {
+            game.spawn_new_piece();
+            assert_eq!(game.current_piece.piece_type, expected);
+            assert_eq!(game.next_queue.len(), 5);
+        }
+    }
+
+    #[test]
+    fn dates_count_from_the_epoch_through_leap_days() {
+        let date = |days| Date::from_days_since_epoch(days).to_string();
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(-1), "1969-12-31");
+        assert_eq!(date(11_017), "2000-03-01");
+        assert_eq!(date(19_782), "2024-02-29");
+        assert_eq!(Date::from_days_since_epoch(19_782).seed(), 20_240_229);
+    }
+
+    #[test]
+    fn preview_count_does_not_change_the_sequence() {
+        let mut short = Game::with_config(GameConfig {
+            seed: Some(3),
+            preview_count: 1,
+            ..GameConfig::default()
+        });
+        let mut long = Game::with_config(GameConfig {
+            seed: Some(3),
+            preview_count: 6,
+            ..GameConfig::default()
+        });
+
+        for _ in 0..20 {
+            assert_eq!(
+                short.current_piece.piece_type,
+                long.current_piece.piece_type
+            );
+            short.spawn_new_piece();
+            long.spawn_new_piece();
+        }
+    }
+
+    #[test]
+    fn update_runs_one_tick_per_elapsed_interval() {
+        let mut game = empty_game();
+        let tick_rate = game.tick_rate();
+
+        game.update(tick_rate / 2);
+        assert_eq!(game.current_piece.y, 0);
+        game.update(tick_rate / 2);
+        assert_eq!(game.current_piece.y, 1);
+
+        game.update(tick_rate * 3);
+        assert_eq!(game.current_piece.y, 4);
+        assert_eq!(game.gravity_accumulator, Duration::ZERO);
+    }
+
+    #[test]
+    fn update_does_not_accumulate_while_paused() {
+        let mut game = empty_game();
+        let tick_rate = game.tick_rate();
+
+        game.toggle_pause();
+        game.update(tick_rate * 5);
+        game.toggle_pause();
+
+        assert_eq!(game.current_piece.y, 0);
+        assert_eq!(game.gravity_accumulator, Duration::ZERO);
+    }
+
+    #[test]
+    fn soft_drop_awards_one_point_per_cell() {
+        let mut game = empty_game();
+
+        assert!(game.soft_drop());
+        assert!(game.soft_drop());
+
+        assert_eq!(game.score, 2);
+        assert_eq!(game.drop_points, 2);
+    }
+
+    #[test]
+    fn blocked_soft_drop_awards_nothing() {
+        let mut game = empty_game();
+        let (_, landing_y) = game.ghost_position();
+        game.current_piece.y = landing_y;
+
+        assert!(!game.soft_drop());
+        assert_eq!(game.score, 0);
+    }
+
+    #[test]
+    fn move_to_column_aligns_left_edge() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+
+        game.move_to_column(0);
+        assert_eq!(game.current_piece.x, 0);
+
+        game.move_to_column(7);
+        assert_eq!(game.current_piece.x, 7);
+
+        // Past the wall the piece just stops at the edge
+        game.move_to_column(20);
+        assert_eq!(game.current_piece.x, 7);
+    }
+
+    #[test]
+    fn move_to_column_stops_at_obstacles() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::O, BOARD_WIDTH);
+        let start_x = game.current_piece.x;
+        game.board.set(1, 1, TetrominoType::Z);
+
+        game.move_to_column(0);
+
+        assert_eq!(game.current_piece.x, 2);
+        assert!(start_x > 2);
+    }
+
+    #[test]
+    fn move_to_column_counts_each_step_like_a_key_press() {
+        let mut clicked = empty_game();
+        clicked.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        let mut pressed = clicked.clone();
+
+        clicked.move_to_column(0);
+        while pressed.current_piece.x > 0 {
+            pressed.handle_action(Action::MoveLeft);
+        }
+
+        assert!(clicked.piece_inputs > 1);
+        assert_eq!(clicked.piece_inputs, pressed.piece_inputs);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut game = Game::with_seed(11);
+        game.hard_drop();
+        game.move_piece(-2, 3);
+        game.rotate_piece();
+        let path = std::env::temp_dir().join(format!("tetris-rs-save-{}.json", std::process::id()));
+
+        game.save_to(&path).unwrap();
+        let mut loaded = Game::load_from(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.board, game.board);
+        assert_eq!(loaded.current_piece, game.current_piece);
+        assert_eq!(loaded.score, game.score);
+        assert_eq!(loaded.next_queue, game.next_queue);
+        for _ in 0..10 {
+            game.spawn_new_piece();
+            loaded.spawn_new_piece();
+            assert_eq!(
+                loaded.current_piece.piece_type,
+                game.current_piece.piece_type
+            );
+        }
+    }
+
+    #[test]
+    fn resuming_mid_bag_deals_the_same_pieces_as_playing_on() {
+        let spawned = |game: &mut Game, count| -> Vec<TetrominoType> {
+            (0..count)
+                .map(|_| {
+                    game.spawn_new_piece();
+                    game.current_piece.piece_type
+                })
+                .collect()
+        };
+        let mut uninterrupted = Game::with_seed(23);
+        let mut saved = Game::with_seed(23);
+        spawned(&mut uninterrupted, 3);
+        spawned(&mut saved, 3);
+        assert_ne!(saved.randomizer, Randomizer::default());
+        let path =
+            std::env::temp_dir().join(format!("tetris-rs-mid-bag-{}.json", std::process::id()));
+
+        saved.save_to(&path).unwrap();
+        let mut loaded = Game::load_from(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.randomizer, uninterrupted.randomizer);
+        // Deep enough to refill the bag a few times from the restored generator
+        assert_eq!(spawned(&mut loaded, 30), spawned(&mut uninterrupted, 30));
+    }
+
+    #[test]
+    fn confirmed_hard_drop_needs_a_second_press() {
+        let window = Duration::from_millis(300);
+        let mut game = empty_game();
+        game.config.confirm_hard_drop = Some(window);
+        game.current_piece = Tetromino::new(TetrominoType::O, BOARD_WIDTH);
+        let start_y = game.current_piece.y;
+
+        game.handle_action(Action::HardDrop);
+        assert_eq!(game.current_piece.y, start_y + 1);
+        assert_eq!(game.pieces_placed, 0);
+
+        // Too late to confirm, so it only counts as a first press again
+        game.last_hard_drop_press = Some(window * 2);
+        game.handle_action(Action::HardDrop);
+        assert_eq!(game.current_piece.y, start_y + 2);
+        assert_eq!(game.pieces_placed, 0);
+
+        game.handle_action(Action::HardDrop);
+        assert_eq!(game.pieces_placed, 1);
+        assert!(game.last_hard_drop_press.is_none());
+    }
+
+    #[test]
+    fn pieces_spawn_in_the_hidden_buffer() {
+        let mut game = Game::with_seed(2);
+        for _ in 0..7 {
+            assert!(game
+                .current_piece_cells()
+                .all(|(_, y)| y < BUFFER_ROWS as i32));
+            game.spawn_new_piece();
+        }
+    }
+
+    #[test]
+    fn tetrominoes_spawn_in_guideline_orientation() {
+        // The two buffer rows of a standard board, as the Guideline spawns into them:
+        // flat side down, and left of center when the piece is three wide
+        let expected = [
+            (TetrominoType::I, ["..........", "...####..."]),
+            (TetrominoType::O, ["....##....", "....##...."]),
+            (TetrominoType::T, ["....#.....", "...###...."]),
+            (TetrominoType::L, [".....#....", "...###...."]),
+            (TetrominoType::J, ["...#......", "...###...."]),
+            (TetrominoType::S, ["....##....", "...##....."]),
+            (TetrominoType::Z, ["...##.....", "....##...."]),
+        ];
+        let mut game = empty_game();
+        for (piece_type, rows) in expected {
+            game.current_piece = Tetromino::new(piece_type, BOARD_WIDTH);
+            let mut cells: Vec<_> = game.current_piece_cells().collect();
+            cells.sort_by_key(|&(x, y)| (y, x));
+            let expected: Vec<_> = rows
+                .iter()
+                .enumerate()
+                .flat_map(|(y, row)| {
+                    row.char_indices()
+                        .filter(|&(_, c)| c == '#')
+                        .map(move |(x, _)| (x as i32, y as i32))
+                })
+                .collect();
+            assert_eq!(cells, expected, "{piece_type:?} spawns out of place");
+            assert_eq!(game.current_piece.rotation, 0);
+        }
+    }
+
+    #[test]
+    fn finesse_minimum_takes_the_shortest_way_round() {
+        // Fewest inputs to the piece turned clockwise `turns` times with its box at `x`
+        let fewest = |piece_type, turns: u8, x| {
+            let mut piece = Tetromino::new(piece_type, BOARD_WIDTH);
+            for _ in 0..turns {
+                piece.shape = piece.rotate_clockwise();
+            }
+            piece.rotation = turns;
+            piece.x = x;
+            finesse_minimum(&piece, BOARD_WIDTH)
+        };
+
+        assert_eq!(fewest(TetrominoType::O, 0, 4), Some(0));
+        // Held to the wall, then tapped back off it, rather than tapped three times
+        let spawn_x = Tetromino::new(TetrominoType::T, BOARD_WIDTH).x;
+        assert_eq!(fewest(TetrominoType::T, 0, spawn_x - 1), Some(1));
+        assert_eq!(fewest(TetrominoType::T, 0, 0), Some(1));
+        assert_eq!(fewest(TetrominoType::T, 0, 1), Some(2));
+        // Standing the I up against either wall is a turn and a hold
+        assert_eq!(fewest(TetrominoType::I, 1, -2), Some(2));
+        assert_eq!(fewest(TetrominoType::I, 1, BOARD_WIDTH as i32 - 3), Some(2));
+        // Either upright S covers the same cells from one column apart
+        assert_eq!(
+            fewest(TetrominoType::S, 3, 4),
+            fewest(TetrominoType::S, 1, 3)
+        );
+    }
+
+    #[test]
+    fn wasted_inputs_count_as_a_finesse_fault() {
+        let mut game = empty_game();
+        game.config.finesse = true;
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        game.handle_action(Action::MoveLeft);
+        game.handle_action(Action::HardDrop);
+        assert_eq!(game.finesse_faults, 0);
+
+        // Tapping all the way to the wall where holding would have done
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        game.piece_inputs = 0;
+        while game.current_piece.x > 0 {
+            game.handle_action(Action::MoveLeft);
+        }
+        game.handle_action(Action::HardDrop);
+        assert_eq!(game.finesse_faults, 1);
+
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        game.handle_action(Action::MoveLeft);
+        while game.current_piece.x > 0 {
+            game.handle_action(Action::RepeatLeft);
+        }
+        assert_eq!(game.piece_inputs, 1);
+        game.handle_action(Action::HardDrop);
+        assert_eq!(game.finesse_faults, 1);
+
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        game.handle_action(Action::MoveLeft);
+        game.handle_action(Action::MoveRight);
+        game.handle_action(Action::RotateCW);
+        game.handle_action(Action::RotateCCW);
+        assert_eq!(game.piece_inputs, 4);
+        game.handle_action(Action::HardDrop);
+        assert_eq!(game.finesse_faults, 2);
+        assert_eq!(game.piece_inputs, 0);
+
+        // Tucked under an overhang, extra inputs are the only way in
+        let bottom = BOARD_HEIGHT - 1;
+        for x in 0..4 {
+            game.board.set(x, bottom - 3, TetrominoType::Z);
+        }
+        game.current_piece = Tetromino::new(TetrominoType::O, BOARD_WIDTH);
+        game.current_piece.x = 0;
+        game.current_piece.y = bottom as i32 - 1;
+        game.piece_inputs = 9;
+        game.handle_action(Action::HardDrop);
+        assert_eq!(game.finesse_faults, 2);
+    }
+
+    #[test]
+    fn lock_progress_runs_from_landing_to_locking() {
+        let mut game = empty_game();
+        assert_eq!(game.lock_progress(), None);
+
+        ground_current_piece(&mut game);
+        game.tick();
+        assert!(game.lock_progress().is_some_and(|progress| progress < 0.5));
+
+        game.lock_timer = Some(LOCK_DELAY / 2);
+        let progress = game.lock_progress().unwrap();
+        assert!((0.5..0.75).contains(&progress), "{progress}");
+
+        game.lock_timer = Some(LOCK_DELAY * 2);
+        assert_eq!(game.lock_progress(), Some(1.0));
+        game.tick();
+        assert_eq!(game.lock_progress(), None);
+    }
+
+    #[test]
+    fn locking_entirely_in_the_buffer_tops_out() {
+        let mut game = empty_game();
+        fill_row_with_gap(&mut game, BUFFER_ROWS, 0);
+        game.current_piece = Tetromino::new(TetrominoType::O, BOARD_WIDTH);
+
+        game.lock_piece();
+
+        assert!(game.game_over);
+    }
+
+    #[test]
+    fn topping_out_fills_the_board_from_the_bottom() {
+        let mut game = empty_game();
+        game.add_garbage(BOARD_HEIGHT, 0);
+        assert!(game.is_topping_out());
+
+        game.state = GameState::GameOverAnimation(Instant::now() - TOP_OUT_DURATION / 2);
+        game.tick();
+        assert!(game.board.is_row_full(BOARD_HEIGHT / 2));
+        assert!(!game.board.is_row_full(BOARD_HEIGHT / 2 - 2));
+
+        game.state = GameState::GameOverAnimation(Instant::now() - TOP_OUT_DURATION);
+        game.step();
+        assert!(!game.is_topping_out());
+        assert!((0..BOARD_HEIGHT).all(|y| game.board.get(0, y) == Cell::Garbage));
+        assert!(game.game_over);
+    }
+
+    #[test]
+    fn top_out_animation_can_be_skipped_or_turned_off() {
+        let mut game = empty_game();
+        game.add_garbage(BOARD_HEIGHT, 0);
+        game.skip_top_out();
+        assert!(!game.is_topping_out());
+        assert!(game.board.is_row_full(0));
+
+        let mut game = Game::with_config(GameConfig {
+            top_out_animation: false,
+            ..GameConfig::default()
+        });
+        game.board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        game.add_garbage(BOARD_HEIGHT, 0);
+        assert!(game.game_over);
+        assert!(!game.is_topping_out());
+    }
+
+    fn zen_game() -> Game {
+        let mut game = empty_game();
+        game.config.mode = GameMode::Zen;
+        game
+    }
+
+    #[test]
+    fn zen_clears_the_top_instead_of_topping_out() {
+        let mut game = zen_game();
+        for y in 0..BOARD_HEIGHT {
+            fill_row_with_gap(&mut game, y, y % BOARD_WIDTH);
+        }
+
+        game.spawn_new_piece();
+
+        assert!(!game.is_over());
+        assert!(
+            (0..ZEN_CLEARED_ROWS).all(|y| (0..BOARD_WIDTH).all(|x| !game.board.is_filled(x, y)))
+        );
+        assert!(game.board.is_filled(1, ZEN_CLEARED_ROWS));
+        assert!(game.is_valid_position(
+            &game.current_piece.shape,
+            game.current_piece.x,
+            game.current_piece.y
+        ));
+    }
+
+    #[test]
+    fn zen_survives_lock_out_and_garbage_overflow() {
+        let mut game = zen_game();
+        fill_row_with_gap(&mut game, BUFFER_ROWS, 0);
+        game.current_piece = Tetromino::new(TetrominoType::O, BOARD_WIDTH);
+        game.lock_piece();
+        assert!(!game.is_over());
+        assert_eq!(game.pieces_placed, 1);
+
+        game.add_garbage(BOARD_HEIGHT, 0);
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn undo_takes_back_locks_in_zen_only() {
+        let mut game = zen_game();
+        game.move_piece(-1, 0);
+        let before = game.snapshot();
+        game.handle_action(Action::HardDrop);
+        let after_drop = game.snapshot();
+        game.handle_action(Action::HardDrop);
+        assert_ne!(game.snapshot(), after_drop);
+
+        game.handle_action(Action::Undo);
+        assert_eq!(game.snapshot(), after_drop);
+        game.handle_action(Action::Undo);
+        assert_eq!(game.snapshot(), before);
+        assert!(!game.undo());
+
+        // The same pieces come again, so dropping again ends up in the same place
+        game.handle_action(Action::HardDrop);
+        assert_eq!(game.snapshot(), after_drop);
+
+        let mut game = empty_game();
+        game.handle_action(Action::HardDrop);
+        let after_drop = game.snapshot();
+        game.handle_action(Action::Undo);
+        assert_eq!(game.snapshot(), after_drop);
+    }
+
+    #[test]
+    fn undo_history_is_capped() {
+        let mut game = zen_game();
+        for _ in 0..UNDO_LIMIT + 5 {
+            game.hard_drop();
+        }
+        let mut undone = 0;
+        while game.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, UNDO_LIMIT);
+    }
+
+    #[test]
+    fn fall_progress_runs_between_gravity_steps() {
+        let mut game = empty_game();
+        assert_eq!(game.fall_progress(), 0.0);
+        game.gravity_accumulator = game.tick_rate() / 4;
+        assert!((game.fall_progress() - 0.25).abs() < 1e-3);
+
+        game.paused = true;
+        assert_eq!(game.fall_progress(), 0.0);
+        game.paused = false;
+        // Resting on the stack it has nowhere to fall to
+        let (_, landing_y) = game.ghost_position();
+        game.current_piece.y = landing_y;
+        assert_eq!(game.fall_progress(), 0.0);
+    }
+
+    #[test]
+    fn state_hash_follows_the_game_not_the_clock() {
+        let play = |drops: usize| {
+            let mut game = Game::with_seed(4);
+            for _ in 0..drops {
+                game.hard_drop();
+            }
+            game
+        };
+        let game = play(6);
+        assert_eq!(game.state_hash(), play(6).state_hash());
+        assert_ne!(game.state_hash(), play(5).state_hash());
+
+        // Same board and pieces, different score
+        let mut scored = play(6);
+        scored.score += 1;
+        assert_ne!(game.state_hash(), scored.state_hash());
+        // Timers and the piece still falling aren't part of it
+        let mut moved = play(6);
+        moved.move_piece(1, 0);
+        moved.lock_timer = Some(Duration::ZERO);
+        assert_eq!(game.state_hash(), moved.state_hash());
+    }
+
+    #[test]
+    fn zen_scores_without_touching_the_high_score() {
+        let mut game = zen_game();
+        game.high_score = 0;
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+
+        assert_eq!(game.score, 100 + PERFECT_CLEAR_BONUS);
+        assert_eq!(game.lines, 1);
+        assert_eq!(game.high_score, 0);
+        assert_eq!(game.config.line_goal(), None);
+    }
+
+    #[test]
+    fn locking_partly_visible_does_not_top_out() {
+        let mut game = empty_game();
+        fill_row_with_gap(&mut game, BUFFER_ROWS + 1, 9);
+        game.current_piece = Tetromino::new(TetrominoType::O, BOARD_WIDTH);
+        game.current_piece.x = 0;
+        game.current_piece.y = 1;
+
+        game.lock_piece();
+
+        assert!(!game.game_over);
+    }
+
+    #[test]
+    fn garbage_lifts_the_falling_piece_clear() {
+        let mut game = empty_game();
+        let start_y = game.ghost_position().1;
+        game.current_piece.y = start_y;
+
+        game.add_garbage(3, 0);
+
+        assert_eq!(game.current_piece.y, start_y - 3);
+        assert_eq!(game.board.get(0, BOARD_HEIGHT - 1), Cell::Empty);
+        assert_eq!(game.board.get(1, BOARD_HEIGHT - 3), Cell::Garbage);
+        assert!(!game.game_over);
+    }
+
+    #[test]
+    fn garbage_overflow_tops_out() {
+        let mut game = empty_game();
+        game.add_garbage(BOARD_HEIGHT, 0);
+        assert!(game.game_over);
+    }
+
+    #[test]
+    fn lines_sent_follow_the_clear_type() {
+        assert_eq!(garbage_lines(None, 1), 0);
+        assert_eq!(garbage_lines(None, 2), 1);
+        assert_eq!(garbage_lines(None, 3), 2);
+        assert_eq!(garbage_lines(None, 4), 4);
+        assert_eq!(garbage_lines(Some(TSpin::Full), 2), 4);
+        assert_eq!(garbage_lines(Some(TSpin::Mini), 1), 0);
+        assert_eq!(garbage_lines(Some(TSpin::Full), 0), 0);
+
+        // A second tetris in a row sends one extra line
+        let mut game = empty_game();
+        clear_tetris(&mut game);
+        assert_eq!(game.lines_sent(), 4);
+        clear_tetris(&mut game);
+        assert_eq!(game.lines_sent(), 5);
+    }
+
+    #[test]
+    fn first_hold_pulls_next_piece() {
+        let mut game = empty_game();
+        let current_type = game.current_piece.piece_type;
+        let next_type = game.next_queue[0];
+
+        game.hold();
+
+        assert_eq!(game.held, [current_type]);
+        assert_eq!(game.current_piece.piece_type, next_type);
+        assert!(!game.can_hold);
+    }
+
+    #[test]
+    fn spawning_a_piece_allows_holding_again() {
+        let mut game = empty_game();
+        game.hold();
+        assert!(!game.can_hold);
+
+        // Nothing to clear on an empty board, so the next piece spawns straight away
+        game.hard_drop();
+
+        assert!(game.can_hold);
+    }
+
+    #[test]
+    fn hold_swaps_with_held_piece_after_lock() {
+        let mut game = empty_game();
+        let first_type = game.current_piece.piece_type;
+        game.hold();
+        game.hard_drop();
+        let current_type = game.current_piece.piece_type;
+
+        game.hold();
+
+        assert_eq!(game.held, [current_type]);
+        assert_eq!(game.current_piece.piece_type, first_type);
+        assert_eq!(game.current_piece.y, 0);
+    }
+
+    #[test]
+    fn cannot_hold_twice_in_a_row() {
+        let mut game = empty_game();
+        game.hold();
+        let current_type = game.current_piece.piece_type;
+        let held = game.held.clone();
+
+        game.hold();
+
+        assert_eq!(game.current_piece.piece_type, current_type);
+        assert_eq!(game.held, held);
+    }
+
+    #[test]
+    fn a_bigger_hold_fills_up_before_giving_back_the_oldest() {
+        let mut game = empty_game();
+        game.config.hold_capacity = 3;
+        let mut set_aside = Vec::new();
+        for _ in 0..3 {
+            set_aside.push(game.current_piece.piece_type);
+            game.hold();
+            game.can_hold = true;
+        }
+        assert_eq!(game.held, set_aside);
+        let next_type = game.next_queue[0];
+        assert_ne!(game.current_piece.piece_type, next_type);
+
+        let fourth = game.current_piece.piece_type;
+        game.hold();
+        assert_eq!(game.current_piece.piece_type, set_aside[0]);
+        assert_eq!(game.held, [set_aside[1], set_aside[2], fourth]);
+        assert_eq!(game.next_queue[0], next_type);
+    }
+
+    #[test]
+    fn four_rotations_return_to_spawn_state() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        game.current_piece.y = 5;
+        let spawn_shape = game.current_piece.shape;
+
+        for _ in 0..4 {
+            game.rotate_piece();
+        }
+
+        assert_eq!(game.current_piece.rotation, 0);
+        assert_eq!(game.current_piece.shape, spawn_shape);
+    }
+
+    #[test]
+    fn every_piece_returns_to_spawn_after_four_turns_either_way() {
+        let pieces = TetrominoType::TETROMINOES
+            .iter()
+            .chain(&TetrominoType::PENTOMINOES);
+        for &piece_type in pieces {
+            let mut game = empty_game();
+            game.current_piece = Tetromino::new(piece_type, BOARD_WIDTH);
+            game.current_piece.y = 5;
+            let (x, y) = (game.current_piece.x, game.current_piece.y);
+
+            for rotate in [Game::rotate_piece, Game::rotate_piece_ccw] {
+                for _ in 0..4 {
+                    rotate(&mut game);
+                }
+
+                assert_eq!(
+                    game.current_piece.shape,
+                    piece_type.shape(),
+                    "{piece_type:?}"
+                );
+                assert_eq!((game.current_piece.x, game.current_piece.y), (x, y));
+                assert_eq!(game.current_piece.rotation, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn shape_mask_converts_to_and_from_rows() {
+        let rows = [
+            [false, true, true],
+            [true, true, false],
+            [false, false, false],
+        ];
+        let shape = Shape::from_rows(rows);
+
+        assert_eq!(shape.to_rows(), rows.map(Vec::from).to_vec());
+        assert_eq!(shape.row_mask(0), 0b110);
+        assert_eq!(shape.row_mask(1), 0b011);
+        assert_eq!(shape.row_mask(2), 0);
+        assert_eq!(
+            shape.cells().collect::<Vec<_>>(),
+            vec![(1, 0), (2, 0), (0, 1), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn t_piece_occupies_srs_cells_in_each_state() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        game.current_piece.y = 5;
+        // Spawn box starts at column 3, so the pivot sits at (4, 6)
+        let expected = [
+            vec![(4, 5), (3, 6), (4, 6), (5, 6)],
+            vec![(4, 5), (4, 6), (5, 6), (4, 7)],
+            vec![(3, 6), (4, 6), (5, 6), (4, 7)],
+            vec![(4, 5), (3, 6), (4, 6), (4, 7)],
+        ];
+
+        for cells in expected {
+            let mut actual: Vec<_> = game.current_piece_cells().collect();
+            actual.sort_by_key(|&(x, y)| (y, x));
+            assert_eq!(actual, cells, "rotation {}", game.current_piece.rotation);
+            game.rotate_piece();
+        }
+    }
+
+    #[test]
+    fn three_wide_pieces_pivot_on_their_center_cell() {
+        let three_wide = [
+            TetrominoType::J,
+            TetrominoType::L,
+            TetrominoType::S,
+            TetrominoType::T,
+            TetrominoType::Z,
+        ];
+        for piece_type in three_wide {
+            let mut game = empty_game();
+            game.current_piece = Tetromino::new(piece_type, BOARD_WIDTH);
+            game.current_piece.y = 5;
+            let pivot = (game.current_piece.x + 1, game.current_piece.y + 1);
+
+            for _ in 0..4 {
+                assert!(
+                    game.current_piece_cells().any(|cell| cell == pivot),
+                    "{piece_type:?} left its pivot in rotation {}",
+                    game.current_piece.rotation
+                );
+                game.rotate_piece();
+            }
+        }
+    }
+
+    #[test]
+    fn rotate_180_flips_piece() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        game.current_piece.y = 5;
+        // The T is symmetric, so a half turn just turns its rows upside down
+        let expected = Shape::from_rows([
+            [false, false, false],
+            [true, true, true],
+            [false, true, false],
+        ]);
+
+        game.rotate_piece_180();
+
+        assert_eq!(game.current_piece.rotation, 2);
+        assert_eq!(game.current_piece.shape, expected);
+    }
+
+    #[test]
+    fn rotate_180_on_floor_kicks_up() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        // Flat side on the floor: the flipped shape's bottom row would be below it
+        game.current_piece.y = BOARD_HEIGHT as i32 - 2;
+        let y = game.current_piece.y;
+
+        game.rotate_piece_180();
+
+        assert_eq!(game.current_piece.rotation, 2);
+        assert_eq!(game.current_piece.y, y - 1);
+    }
+
+    #[test]
+    fn rotation_against_wall_uses_kick_offset() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::I, BOARD_WIDTH);
+        game.current_piece.y = 5;
+        game.rotate_piece();
+        // Vertical I in column 2 of its box, pushed flush against the left wall
+        game.current_piece.x = -2;
+
+        game.rotate_piece();
+
+        assert_eq!(game.current_piece.rotation, 2);
+        assert!(game.is_valid_position(
+            &game.current_piece.shape,
+            game.current_piece.x,
+            game.current_piece.y
+        ));
+        assert!(game.current_piece.x >= 0);
+    }
+
+    /// Puts `piece_type` in rotation state `from` and fills every cell except the ones
+    /// it covers and the ones it would cover in state `to` moved by `offset` (board
+    /// coordinates, y down), so only that one placement is left to rotate into
+    fn wall_in_rotation(
+        game: &mut Game,
+        piece_type: TetrominoType,
+        from: u8,
+        to: u8,
+        offset: (i32, i32),
+    ) {
+        let (x, y) = (3, 8);
+        let mut piece = Tetromino::new(piece_type, BOARD_WIDTH);
+        for _ in 0..from {
+            piece.shape = piece.rotate_clockwise();
+        }
+        piece.x = x;
+        piece.y = y;
+        piece.rotation = from;
+        let mut rotated = piece_type.shape();
+        for _ in 0..to {
+            rotated = rotated.rotate_clockwise();
+        }
+
+        let box_cells = |shape: &Shape, x: i32, y: i32| {
+            shape
+                .cells()
+                .map(move |(dx, dy)| (x + dx as i32, y + dy as i32))
+                .collect::<Vec<_>>()
+        };
+        let mut free = box_cells(&piece.shape, x, y);
+        free.extend(box_cells(&rotated, x + offset.0, y + offset.1));
+        for by in 0..BOARD_HEIGHT {
+            for bx in 0..BOARD_WIDTH {
+                if !free.contains(&(bx as i32, by as i32)) {
+                    game.board.set(bx, by, TetrominoType::Z);
+                }
+            }
+        }
+        game.current_piece = piece;
+    }
+
+    fn rotate_towards(game: &mut Game, to: u8) {
+        if to == (game.current_piece.rotation + 1) % 4 {
+            game.rotate_piece();
+        } else {
+            game.rotate_piece_ccw();
+        }
+    }
+
+    #[test]
+    fn jlstz_rotations_fall_back_to_the_last_kick() {
+        // (from, to, last offset in board coordinates)
+        let last_kicks = [
+            (0, 1, (-1, 2)),
+            (0, 3, (1, 2)),
+            (1, 2, (1, -2)),
+            (1, 0, (1, -2)),
+            (2, 3, (1, 2)),
+            (2, 1, (-1, 2)),
+            (3, 0, (-1, -2)),
+            (3, 2, (-1, -2)),
+        ];
+        for (from, to, offset) in last_kicks {
+            let mut game = empty_game();
+            wall_in_rotation(&mut game, TetrominoType::T, from, to, offset);
+
+            rotate_towards(&mut game, to);
+
+            assert_eq!(game.current_piece.rotation, to, "{from} -> {to}");
+            assert_eq!(
+                (game.current_piece.x, game.current_piece.y),
+                (3 + offset.0, 8 + offset.1),
+                "{from} -> {to}"
+            );
+        }
+    }
+
+    #[test]
+    fn i_piece_uses_its_own_kick_table() {
+        // Every kick of every rotation, each tried on a board where only it fits
+        for from in 0..4u8 {
+            for to in [(from + 1) % 4, (from + 3) % 4] {
+                for &(dx, dy) in srs_kicks(TetrominoType::I, from, to) {
+                    let mut game = empty_game();
+                    wall_in_rotation(&mut game, TetrominoType::I, from, to, (dx, -dy));
+
+                    rotate_towards(&mut game, to);
+
+                    assert_eq!(game.current_piece.rotation, to);
+                    assert_eq!(
+                        (game.current_piece.x, game.current_piece.y),
+                        (3 + dx, 8 - dy),
+                        "{from} -> {to} kick ({dx}, {dy})"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn i_piece_cannot_use_jlstz_kicks() {
+        // The T's second kick for 0 -> R is one column left, which the I never tries
+        let mut game = empty_game();
+        wall_in_rotation(&mut game, TetrominoType::I, 0, 1, (-1, 0));
+
+        game.rotate_piece();
+
+        assert_eq!(game.current_piece.rotation, 0);
+        assert_eq!(game.current_piece.x, 3);
+    }
+
+    #[test]
+    fn t_spin_triple_uses_the_last_kick() {
+        let mut game = empty_game();
+        let bottom = BOARD_HEIGHT - 1;
+        // The roof over the well, then the well itself with its notch on the left
+        for x in 3..BOARD_WIDTH {
+            game.board.set(x, bottom - 4, TetrominoType::Z);
+        }
+        for x in 4..BOARD_WIDTH {
+            game.board.set(x, bottom - 3, TetrominoType::Z);
+        }
+        fill_row_with_gap(&mut game, bottom - 2, 3);
+        for x in 4..BOARD_WIDTH {
+            game.board.set(x, bottom - 1, TetrominoType::Z);
+        }
+        game.board.set(0, bottom - 1, TetrominoType::Z);
+        game.board.set(1, bottom - 1, TetrominoType::Z);
+        fill_row_with_gap(&mut game, bottom, 3);
+        // Resting flat on the left side of the roof's edge
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        game.current_piece.x = 1;
+        game.current_piece.y = bottom as i32 - 4;
+
+        game.rotate_piece_ccw();
+
+        // Kick (1, -2): one right and two down, pointing left into the notch
+        assert_eq!(game.current_piece.rotation, 3);
+        assert_eq!(
+            (game.current_piece.x, game.current_piece.y),
+            (2, bottom as i32 - 2)
+        );
+        game.freeze_piece();
+        game.clear_lines();
+        assert_eq!(game.lines, 3);
+        assert!(matches!(game.last_t_spin, Some((TSpin::Full, _))));
+    }
+
+    fn ground_current_piece(game: &mut Game) {
+        let (_, landing_y) = game.ghost_position();
+        game.current_piece.y = landing_y;
+    }
+
+    #[test]
+    fn grounded_piece_waits_for_lock_delay() {
+        let mut game = empty_game();
+        ground_current_piece(&mut game);
+        let piece_type = game.current_piece.piece_type;
+
+        game.tick();
+
+        assert!(game.lock_timer.is_some());
+        assert_eq!(game.current_piece.piece_type, piece_type);
+        assert!(game
+            .board
+            .rows()
+            .iter()
+            .flatten()
+            .all(|cell| *cell == Cell::Empty));
+    }
+
+    #[test]
+    fn soft_dropping_to_the_floor_still_waits_for_lock_delay() {
+        let mut game = empty_game();
+        let piece_type = game.current_piece.piece_type;
+
+        // As a held soft drop at a high factor would, all in one go
+        let mut drops = 0;
+        while game.soft_drop() {
+            drops += 1;
+        }
+        assert!(!game.soft_drop());
+        game.tick();
+
+        assert_eq!(game.score, drops);
+        assert_eq!(game.current_piece.piece_type, piece_type);
+        assert!(game.lock_timer.is_some());
+        // Sliding along the floor is still possible before it locks
+        assert!(game.move_piece(1, 0));
+    }
+
+    #[test]
+    fn grounded_piece_locks_after_delay_expires() {
+        let mut game = empty_game();
+        ground_current_piece(&mut game);
+        game.lock_timer = Some(LOCK_DELAY);
+
+        game.tick();
+
+        assert!(game.lock_timer.is_none());
+        assert!(game
+            .board
+            .rows()
+            .iter()
+            .flatten()
+            .any(|cell| *cell != Cell::Empty));
+    }
+
+    #[test]
+    fn lock_delay_follows_the_config() {
+        let mut game = Game::with_config(GameConfig {
+            seed: Some(1),
+            ..GameConfig::with_difficulty(Difficulty::Easy)
+        });
+        ground_current_piece(&mut game);
+        game.lock_timer = Some(LOCK_DELAY);
+
+        game.tick();
+
+        // Half a second is not enough on Easy
+        assert!(game.lock_timer.is_some());
+    }
+
+    #[test]
+    fn difficulty_presets_bundle_the_settings() {
+        let easy = GameConfig::with_difficulty(Difficulty::Easy);
+        assert_eq!(easy.gravity_table, GravityTable::Guideline);
+        assert!(easy.lock_delay > LOCK_DELAY);
+        assert!(easy.show_ghost);
+
+        let hard = Game::new(Difficulty::Hard);
+        assert_eq!(hard.config.difficulty, Difficulty::Hard);
+        assert_eq!(hard.level, 10);
+        assert_eq!(hard.config.gravity_table, GravityTable::Nes);
+        assert!(hard.config.lock_delay < LOCK_DELAY);
+        assert!(!hard.config.show_ghost);
+    }
+
+    #[test]
+    fn moving_while_grounded_resets_lock_timer_up_to_cap() {
+        let mut game = empty_game();
+        ground_current_piece(&mut game);
+        let expired = LOCK_DELAY;
+        game.lock_timer = Some(expired);
+
+        assert!(game.move_piece(1, 0));
+        assert!(game.lock_timer.unwrap() < expired);
+        assert_eq!(game.lock_resets, 1);
+
+        game.lock_resets = MAX_LOCK_RESETS;
+        game.lock_timer = Some(expired);
+        assert!(game.move_piece(-1, 0));
+        assert_eq!(game.lock_timer, Some(expired));
+    }
+
+    #[test]
+    fn spinning_past_the_reset_cap_still_locks() {
+        let mut game = empty_game();
+        game.config.max_lock_resets = 4;
+        game.current_piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        ground_current_piece(&mut game);
+        game.tick();
+        assert!(game.lock_timer.is_some());
+
+        // Half the lock delay goes by before each rotation, which would stall
+        // forever if every one of them restarted the timer
+        let mut rotations = 0;
+        while game.pieces_placed == 0 && rotations < 100 {
+            if let Some(rested) = &mut game.lock_timer {
+                *rested += LOCK_DELAY / 2;
+            }
+            game.rotate_piece();
+            rotations += 1;
+            game.tick();
+        }
+
+        assert_eq!(game.pieces_placed, 1);
+        assert!(rotations > game.config.max_lock_resets);
+        assert_eq!(game.lock_resets, 0);
+    }
+
+    #[test]
+    fn completed_rows_flash_before_clearing() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::I, BOARD_WIDTH);
+        for x in 4..BOARD_WIDTH {
+            game.board.set(x, BOARD_HEIGHT - 1, TetrominoType::Z);
+        }
+        game.current_piece.x = 0;
+
+        game.hard_drop();
+
+        assert_eq!(game.clearing.as_ref().unwrap().0, vec![BOARD_HEIGHT - 1]);
+        assert!(game.board.is_row_full(BOARD_HEIGHT - 1));
+        assert_eq!(game.lines, 0);
+
+        game.clearing.as_mut().unwrap().1 = LINE_CLEAR_DELAY;
+        game.tick();
+
+        assert!(game.clearing.is_none());
+        assert_eq!(game.lines, 1);
+        assert!(!game.board.is_row_full(BOARD_HEIGHT - 1));
+    }
+
+    #[test]
+    fn clears_single_line() {
+        let mut game = empty_game();
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+
+        game.clear_lines();
+
+        // Nothing else on the board, so every clear here is a perfect clear too
+        assert_rows_empty(&game, 0..BOARD_HEIGHT);
+        assert_eq!(game.score, 100 + PERFECT_CLEAR_BONUS);
+    }
+
+    #[test]
+    fn emptying_the_board_is_a_perfect_clear() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::O, BOARD_WIDTH);
+        let x = game.current_piece.x as usize;
+        for y in BOARD_HEIGHT - 2..BOARD_HEIGHT {
+            for column in (0..BOARD_WIDTH).filter(|&column| column != x && column != x + 1) {
+                game.board.set(column, y, TetrominoType::Z);
+            }
+        }
+        game.hard_drop();
+        game.clearing.as_mut().unwrap().1 = LINE_CLEAR_DELAY;
+        let score = game.score;
+
+        let events = game.tick();
+
+        assert!(events.contains(&GameEvent::PerfectClear));
+        assert_eq!(game.score - score, 300 + PERFECT_CLEAR_BONUS);
+        assert!(game.last_perfect_clear.is_some());
+        assert_eq!(
+            game.score_log.back().map(|entry| entry.reason),
+            Some(ScoreReason::PerfectClear)
+        );
+    }
+
+    #[test]
+    fn clearing_with_blocks_left_over_is_no_perfect_clear() {
+        let mut game = empty_game();
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.board.set(0, BOARD_HEIGHT - 2, TetrominoType::J);
+
+        game.clear_lines();
+
+        assert_eq!(game.score, 100);
+        assert!(!game.take_events().contains(&GameEvent::PerfectClear));
+    }
+
+    #[test]
+    fn clears_double() {
+        let mut game = empty_game();
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        fill_row(&mut game, BOARD_HEIGHT - 2);
+
+        game.clear_lines();
+
+        assert_rows_empty(&game, 0..BOARD_HEIGHT);
+        assert_eq!(game.score, 300 + PERFECT_CLEAR_BONUS);
+    }
+
+    #[test]
+    fn clears_triple() {
+        let mut game = empty_game();
+        for y in BOARD_HEIGHT - 3..BOARD_HEIGHT {
+            fill_row(&mut game, y);
+        }
+
+        game.clear_lines();
+
+        assert_rows_empty(&game, 0..BOARD_HEIGHT);
+        assert_eq!(game.score, 500 + PERFECT_CLEAR_BONUS);
+    }
+
+    #[test]
+    fn clears_tetris() {
+        let mut game = empty_game();
+        for y in BOARD_HEIGHT - 4..BOARD_HEIGHT {
+            fill_row(&mut game, y);
+        }
+
+        game.clear_lines();
+
+        assert_rows_empty(&game, 0..BOARD_HEIGHT);
+        assert_eq!(game.score, 800 + PERFECT_CLEAR_BONUS);
+        assert_eq!(game.lines, 4);
+    }
+
+    fn clear_tetris(game: &mut Game) {
+        for y in BOARD_HEIGHT - 4..BOARD_HEIGHT {
+            fill_row(game, y);
+        }
+        game.clear_lines();
+    }
+
+    #[test]
+    fn cascade_gravity_chains_clears() {
+        let mut game = empty_game();
+        game.config.gravity = GravityMode::Cascade;
+        let bottom = BOARD_HEIGHT - 1;
+        fill_row_with_gap(&mut game, bottom, 3);
+        fill_row(&mut game, bottom - 1);
+        game.board.set(3, bottom - 2, TetrominoType::J);
+
+        game.clear_lines();
+
+        // The loose block drops into the gap and completes the bottom row too
+        assert_eq!(game.lines, 2);
+        assert_rows_empty(&game, 0..BOARD_HEIGHT);
+    }
+
+    #[test]
+    fn classic_gravity_leaves_blocks_floating() {
+        let mut game = empty_game();
+        let bottom = BOARD_HEIGHT - 1;
+        fill_row_with_gap(&mut game, bottom, 3);
+        fill_row(&mut game, bottom - 1);
+        game.board.set(3, bottom - 2, TetrominoType::J);
+
+        game.clear_lines();
+
+        assert_eq!(game.lines, 1);
+        assert!(game.board.is_filled(3, bottom - 1));
+        assert!(!game.board.is_filled(3, bottom));
+    }
+
+    #[test]
+    fn back_to_back_tetris_awards_bonus() {
+        let mut game = empty_game();
+
+        clear_tetris(&mut game);
+        clear_tetris(&mut game);
+
+        // Second tetris also starts a combo, and both empty the board
+        assert_eq!(game.score, 800 + 1200 + 50 + 2 * PERFECT_CLEAR_BONUS);
+        assert!(game.back_to_back);
+    }
+
+    #[test]
+    fn non_tetris_clear_breaks_back_to_back() {
+        let mut game = empty_game();
+        clear_tetris(&mut game);
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+        assert!(!game.back_to_back);
+
+        clear_tetris(&mut game);
+
+        // Plain tetris score, plus combo bonuses for the second and third clears
+        // and a perfect clear for each
+        assert_eq!(
+            game.score,
+            800 + 100 + 800 + 50 + 100 + 3 * PERFECT_CLEAR_BONUS
+        );
+    }
+
+    #[test]
+    fn lock_without_clear_keeps_back_to_back() {
+        let mut game = empty_game();
+        clear_tetris(&mut game);
+
+        game.clear_lines();
+
+        assert!(game.back_to_back);
+    }
+
+    #[test]
+    fn consecutive_clears_build_combo_bonus() {
+        let mut game = empty_game();
+
+        // Each clear empties the board, adding a perfect clear bonus on top
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+        assert_eq!(game.combo, 0);
+        assert_eq!(game.score, 100 + PERFECT_CLEAR_BONUS);
+
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+        assert_eq!(game.combo, 1);
+        assert_eq!(game.score, 100 + 100 + 50 + 2 * PERFECT_CLEAR_BONUS);
+
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+        assert_eq!(game.combo, 2);
+        assert_eq!(game.score, 250 + 100 + 100 + 3 * PERFECT_CLEAR_BONUS);
+    }
+
+    #[test]
+    fn lock_without_clear_resets_combo() {
+        let mut game = empty_game();
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+
+        game.clear_lines();
+
+        assert_eq!(game.combo, -1);
+    }
+
+    #[test]
+    fn longest_combo_outlasts_the_chain() {
+        let mut game = empty_game();
+        for _ in 0..3 {
+            fill_row(&mut game, BOARD_HEIGHT - 1);
+            game.clear_lines();
+        }
+        game.clear_lines();
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+
+        assert_eq!(game.combo, 0);
+        assert_eq!(game.max_combo, 2);
+    }
+
+    #[test]
+    fn rates_count_placed_pieces_and_lines_over_play_time() {
+        let mut game = empty_game();
+        assert_eq!(game.pieces_per_second(), 0.0);
+
+        game.hard_drop();
+        game.hard_drop();
+        assert_eq!(game.pieces_placed, 2);
+
+        game.lines = 3;
+        game.clock = Duration::from_secs(60);
+        assert!((game.pieces_per_second() - 2.0 / 60.0).abs() < 1e-3);
+        assert!((game.lines_per_minute() - 3.0).abs() < 0.1);
+    }
+
+    /// Places a T piece at `(x, y)` in the given rotation state as if rotated into place
+    fn place_rotated_t(game: &mut Game, x: i32, y: i32, rotation: u8) {
+        let mut piece = Tetromino::new(TetrominoType::T, BOARD_WIDTH);
+        for _ in 0..rotation {
+            piece.shape = piece.rotate_clockwise();
+        }
+        piece.x = x;
+        piece.y = y;
+        piece.rotation = rotation;
+        game.current_piece = piece;
+        game.last_move_was_rotation = true;
+    }
+
+    #[test]
+    fn t_spin_double_awards_bonus() {
+        let mut game = empty_game();
+        let bottom = BOARD_HEIGHT - 1;
+        fill_row_with_gap(&mut game, bottom, 1);
+        for x in 3..BOARD_WIDTH {
+            game.board.set(x, bottom - 1, TetrominoType::Z);
+        }
+        // Overhang covering one of the back corners
+        game.board.set(2, bottom - 2, TetrominoType::Z);
+        place_rotated_t(&mut game, 0, bottom as i32 - 2, 2);
+
+        game.freeze_piece();
+        game.clear_lines();
+
+        assert_eq!(game.lines, 2);
+        assert_eq!(game.score, 1200);
+        assert!(matches!(game.last_t_spin, Some((TSpin::Full, _))));
+    }
+
+    #[test]
+    fn t_spin_mini_without_lines() {
+        let mut game = empty_game();
+        let bottom = BOARD_HEIGHT - 1;
+        // The floor covers both back corners, this block one front corner
+        game.board.set(0, bottom - 1, TetrominoType::Z);
+        place_rotated_t(&mut game, 0, bottom as i32 - 1, 0);
+
+        game.freeze_piece();
+        game.clear_lines();
+
+        assert_eq!(game.score, 100);
+        assert!(matches!(game.last_t_spin, Some((TSpin::Mini, _))));
+    }
+
+    #[test]
+    fn t_piece_moved_into_place_is_not_a_t_spin() {
+        let mut game = empty_game();
+        let bottom = BOARD_HEIGHT - 1;
+        game.board.set(0, bottom - 1, TetrominoType::Z);
+        place_rotated_t(&mut game, 0, bottom as i32 - 1, 0);
+        game.last_move_was_rotation = false;
+
+        game.freeze_piece();
+        game.clear_lines();
+
+        assert_eq!(game.score, 0);
+        assert!(game.last_t_spin.is_none());
+    }
+
+    #[test]
+    fn sprint_ends_with_win_at_forty_lines() {
+        let mut game = Game::with_config(GameConfig {
+            mode: GameMode::Sprint,
+            ..GameConfig::default()
+        });
+        game.board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        game.lines = SPRINT_LINES - 1;
+
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+
+        assert!(game.won);
+        assert!(!game.game_over);
+        assert!(game.is_over());
+        let finish_time = game.elapsed();
+        assert_eq!(game.elapsed(), finish_time);
+    }
+
+    #[test]
+    fn survival_garbage_rises_faster_and_faster() {
+        let mut game = empty_game();
+        game.config.mode = GameMode::Survival;
+        let first = game.rise_interval();
+        game.advance_rise(first - Duration::from_millis(1));
+        assert_eq!(game.garbage_left(), 0);
+        game.advance_rise(Duration::from_millis(1));
+        assert_eq!(game.garbage_left(), 1);
+        assert_eq!(game.score, SURVIVAL_RISE_POINTS);
+        assert_eq!(game.time_to_next_rise(), Some(first));
+
+        for _ in 1..SURVIVAL_RISES_PER_LEVEL {
+            let interval = game.rise_interval();
+            game.advance_rise(interval);
+        }
+        assert_eq!(game.rise_level, 1);
+        assert!(game.rise_interval() < first);
+        game.rise_level = 100;
+        assert_eq!(game.rise_interval(), SURVIVAL_MIN_RISE_INTERVAL);
+    }
+
+    #[test]
+    fn survival_rises_wait_for_a_clear_to_finish() {
+        let mut game = empty_game();
+        game.config.mode = GameMode::Survival;
+        game.clearing = Some((vec![BOARD_HEIGHT - 1], Duration::ZERO));
+        let interval = game.rise_interval();
+        game.advance_rise(interval * 2);
+        assert_eq!(game.garbage_left(), 0);
+        assert_eq!(game.time_to_next_rise(), Some(Duration::ZERO));
+
+        game.clearing = None;
+        game.advance_rise(Duration::ZERO);
+        assert_eq!(game.garbage_left(), 1);
+    }
+
+    #[test]
+    fn garbage_only_rises_in_survival() {
+        let mut game = empty_game();
+        game.advance_rise(SURVIVAL_RISE_INTERVAL * 3);
+        assert_eq!(game.garbage_left(), 0);
+        assert_eq!(game.time_to_next_rise(), None);
+    }
+
+    #[test]
+    fn dig_is_won_by_clearing_the_starting_garbage() {
+        let mut game = Game::with_config(GameConfig {
+            mode: GameMode::Dig,
+            garbage_rows: 3,
+            seed: Some(7),
+            ..GameConfig::default()
+        });
+        let height = game.board.height();
+        assert_eq!(game.garbage_left(), 3);
+        for y in height - 3..height {
+            let gaps = (0..BOARD_WIDTH).filter(|&x| !game.board.is_filled(x, y));
+            assert_eq!(gaps.count(), 1, "row {y}");
+        }
+
+        fill_row(&mut game, height - 1);
+        game.clear_lines();
+        assert_eq!(game.garbage_left(), 2);
+        assert!(!game.is_over());
+
+        fill_row(&mut game, height - 1);
+        fill_row(&mut game, height - 2);
+        game.clear_lines();
+        assert!(game.won);
+        assert!(!game.game_over);
+        assert_eq!(game.config.line_goal(), None);
+    }
+
+    #[test]
+    fn marathon_continues_past_forty_lines() {
+        let mut game = empty_game();
+        game.lines = SPRINT_LINES - 1;
+
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn level_never_drops_below_the_start_level() {
+        let mut game = Game::with_config(GameConfig {
+            start_level: 5,
+            ..GameConfig::default()
+        });
+        game.board = Board::new(BOARD_WIDTH, BOARD_HEIGHT);
+        assert_eq!(game.level, 5);
+        let start_rate = game.tick_rate();
+        assert!(start_rate < Game::new(Difficulty::Normal).tick_rate());
+
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+        assert_eq!(game.level, 5);
+        assert_eq!(game.tick_rate(), start_rate);
+
+        // Past the start level, lines take over again
+        game.lines = 6 * LINES_PER_LEVEL - 1;
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+        assert_eq!(game.level, 6);
+    }
+
+    #[test]
+    fn marathon_is_won_at_its_goal() {
+        let mut game = empty_game();
+        game.lines = MARATHON_LINES - 1;
+
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+
+        assert!(game.won);
+        assert!(!game.game_over);
+    }
+
+    #[test]
+    fn endless_marathon_has_no_goal() {
+        let mut game = empty_game();
+        game.config.marathon_goal = None;
+        game.lines = MARATHON_LINES;
+
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn clearing_middle_row_shifts_rows_above_down() {
+        let mut game = empty_game();
+        fill_row_with_gap(&mut game, BOARD_HEIGHT - 1, 0);
+        fill_row(&mut game, BOARD_HEIGHT - 2);
+        fill_row_with_gap(&mut game, BOARD_HEIGHT - 3, 5);
+
+        game.clear_lines();
+
+        assert_eq!(game.score, 100);
+        // Bottom row is untouched
+        assert_eq!(game.board.get(0, BOARD_HEIGHT - 1), Cell::Empty);
+        assert_eq!(
+            game.board.get(1, BOARD_HEIGHT - 1),
+            Cell::Filled(TetrominoType::Z)
+        );
+        // Row above the cleared one moved down into its place
+        assert_eq!(game.board.get(5, BOARD_HEIGHT - 2), Cell::Empty);
+        assert_eq!(
+            game.board.get(0, BOARD_HEIGHT - 2),
+            Cell::Filled(TetrominoType::Z)
+        );
+        assert_rows_empty(&game, 0..BOARD_HEIGHT - 2);
+    }
+
+    #[test]
+    fn no_clear_leaves_board_and_score_unchanged() {
+        let mut game = empty_game();
+        fill_row_with_gap(&mut game, BOARD_HEIGHT - 1, 3);
+        fill_row_with_gap(&mut game, BOARD_HEIGHT - 2, 7);
+        let before = game.board.clone();
+
+        game.clear_lines();
+
+        assert_eq!(game.board, before);
+        assert_eq!(game.score, 0);
+        assert_eq!(game.lines, 0);
+    }
+
+    #[test]
+    fn actions_report_their_events_once() {
+        let mut game = empty_game();
+
+        assert!(matches!(
+            game.handle_action(Action::HardDrop).as_slice(),
+            [
+                GameEvent::HardDropped,
+                GameEvent::Scored(ScoreEntry {
+                    piece: 1,
+                    reason: ScoreReason::HardDrop,
+                    ..
+                }),
+                GameEvent::PieceLocked
+            ]
+        ));
+        assert!(game.handle_action(Action::Pause).is_empty());
+    }
+
+    #[test]
+    fn gravity_reports_the_lock_then_the_clear() {
+        let mut game = empty_game();
+        let bottom = BOARD_HEIGHT - 1;
+        // Leave exactly the gap the falling piece will land in
+        let (ghost_x, ghost_y) = game.ghost_position();
+        let landing: Vec<_> = game
+            .current_piece
+            .shape
+            .cells()
+            .map(|(x, y)| (ghost_x + x as i32, ghost_y + y as i32))
+            .collect();
+        for x in 0..BOARD_WIDTH {
+            if !landing.contains(&(x as i32, bottom as i32)) {
+                game.board.set(x, bottom, TetrominoType::Z);
+            }
+        }
+        // A block off to the side survives the clear, so it's never a perfect clear
+        let spare = (0..BOARD_WIDTH)
+            .find(|&x| landing.iter().all(|&(landing_x, _)| landing_x != x as i32))
+            .unwrap();
+        game.board.set(spare, bottom - 1, TetrominoType::Z);
+
+        while game.current_piece.y < ghost_y {
+            assert!(game.tick().is_empty());
+        }
+        // Grounding starts the lock delay, and only its expiry locks
+        assert!(game.tick().is_empty());
+        game.lock_timer = Some(LOCK_DELAY);
+        assert_eq!(game.tick(), vec![GameEvent::PieceLocked]);
+
+        if let Some((_, flashed)) = &mut game.clearing {
+            *flashed += LINE_CLEAR_DELAY;
+        }
+        assert_eq!(
+            game.tick(),
+            vec![
+                GameEvent::LinesCleared(1),
+                GameEvent::Scored(ScoreEntry {
+                    piece: 1,
+                    reason: ScoreReason::Clear {
+                        lines: 1,
+                        t_spin: None,
+                        back_to_back: false,
+                    },
+                    points: 100,
+                })
+            ]
+        );
+    }
+
+    #[test]
+    fn t_spin_is_reported_before_its_lines() {
+        let mut game = empty_game();
+        let bottom = BOARD_HEIGHT - 1;
+        fill_row_with_gap(&mut game, bottom, 1);
+        for x in 3..BOARD_WIDTH {
+            game.board.set(x, bottom - 1, TetrominoType::Z);
+        }
+        game.board.set(2, bottom - 2, TetrominoType::Z);
+        place_rotated_t(&mut game, 0, bottom as i32 - 2, 2);
+
+        game.freeze_piece();
+        game.clear_lines();
+
+        assert_eq!(
+            game.take_events(),
+            vec![
+                GameEvent::TSpin(TSpin::Full),
+                GameEvent::LinesCleared(2),
+                GameEvent::Scored(ScoreEntry {
+                    piece: 1,
+                    reason: ScoreReason::Clear {
+                        lines: 2,
+                        t_spin: Some(TSpin::Full),
+                        back_to_back: false,
+                    },
+                    points: 1200,
+                }),
+                GameEvent::GarbageSent(4),
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_to_next_level_count_from_the_start_level() {
+        let game = Game::with_config(GameConfig {
+            start_level: 3,
+            ..GameConfig::default()
+        });
+        assert_eq!(game.lines_to_next_level(), 4 * LINES_PER_LEVEL);
+    }
+
+    #[test]
+    fn crossing_a_level_reports_it_once() {
+        let mut game = empty_game();
+        game.lines = LINES_PER_LEVEL - 1;
+        assert_eq!(game.lines_to_next_level(), 1);
+
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+        assert!(game.last_level_up.is_some());
+        assert_eq!(game.lines_to_next_level(), LINES_PER_LEVEL);
+        let levels = |events: Vec<GameEvent>| -> Vec<GameEvent> {
+            events
+                .into_iter()
+                .filter(|event| matches!(event, GameEvent::LevelUp(_)))
+                .collect()
+        };
+        assert_eq!(levels(game.take_events()), vec![GameEvent::LevelUp(1)]);
+
+        fill_row(&mut game, BOARD_HEIGHT - 1);
+        game.clear_lines();
+        assert!(levels(game.take_events()).is_empty());
+    }
+
+    #[test]
+    fn hard_drop_leaves_a_trail_above_the_landed_piece() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::O, BOARD_WIDTH);
+        let (x, y) = (game.current_piece.x, game.current_piece.y);
+        let score = game.score;
+
+        game.hard_drop();
+
+        let (trail, _) = game.last_hard_drop.unwrap();
+        assert_eq!((trail.left, trail.right), (x, x + 1));
+        assert_eq!(trail.from_y, y);
+        assert_eq!(trail.to_y, BOARD_HEIGHT as i32 - 2);
+        assert!(trail.contains(x + 1, y));
+        assert!(!trail.contains(x + 2, y));
+        assert!(!trail.contains(x, trail.to_y));
+        // Only the drop distance scores, the trail itself is just for show
+        assert_eq!(game.score - score, 2 * (trail.to_y - y) as u32);
+    }
+
+    fn assert_tick_ms(table: GravityTable, level: u32, expected_ms: f64) {
+        let ms = table.tick_rate(level).as_secs_f64() * 1000.0;
+        assert!(
+            (ms - expected_ms).abs() < 0.05,
+            "{table:?} level {level}: {ms}ms, expected {expected_ms}ms"
+        );
+    }
+
+    #[test]
+    fn linear_table_speeds_up_40ms_per_level() {
+        assert_tick_ms(GravityTable::Linear, 0, 500.0);
+        assert_tick_ms(GravityTable::Linear, 5, 300.0);
+        assert_tick_ms(GravityTable::Linear, 11, 60.0);
+        assert_tick_ms(GravityTable::Linear, 30, 50.0);
+    }
+
+    #[test]
+    fn nes_table_counts_frames_per_row() {
+        let frame_ms = 1000.0 / NES_FRAME_RATE;
+        assert_tick_ms(GravityTable::Nes, 0, 48.0 * frame_ms);
+        assert_tick_ms(GravityTable::Nes, 8, 8.0 * frame_ms);
+        assert_tick_ms(GravityTable::Nes, 9, 6.0 * frame_ms);
+        assert_tick_ms(GravityTable::Nes, 18, 3.0 * frame_ms);
+        assert_tick_ms(GravityTable::Nes, 19, 2.0 * frame_ms);
+        assert_tick_ms(GravityTable::Nes, 29, frame_ms);
+        assert_tick_ms(GravityTable::Nes, 100, frame_ms);
+    }
+
+    #[test]
+    fn guideline_table_follows_its_formula_up_to_20g() {
+        assert_tick_ms(GravityTable::Guideline, 0, 1000.0);
+        assert_tick_ms(GravityTable::Guideline, 1, 793.0);
+        assert_tick_ms(GravityTable::Guideline, 9, 0.737_f64.powi(9) * 1000.0);
+        assert_tick_ms(GravityTable::Guideline, 14, 0.702_f64.powi(14) * 1000.0);
+        assert_tick_ms(GravityTable::Guideline, 19, 1000.0 / 60.0 / 20.0);
+        assert_tick_ms(GravityTable::Guideline, 1000, 1000.0 / 60.0 / 20.0);
+    }
+
+    #[test]
+    fn game_ticks_at_its_tables_rate() {
+        let mut game = Game::with_config(GameConfig {
+            gravity_table: GravityTable::Nes,
+            start_level: 19,
+            ..GameConfig::default()
+        });
+        assert_eq!(game.tick_rate(), GravityTable::Nes.tick_rate(19));
+
+        game.state = GameState::Playing;
+        game.board = Board::new(BOARD_WIDTH, BOARD_HEIGHT + BUFFER_ROWS);
+        let start_y = game.current_piece.y;
+        // Ten NES frames at two frames per row, rounded up to a whole step
+        game.update(Duration::from_secs_f64(10.0 / NES_FRAME_RATE) + SIMULATION_STEP);
+        assert_eq!(game.current_piece.y, start_y + 5);
+    }
+
+    #[test]
+    fn score_log_merges_soft_drops_and_notes_each_piece() {
+        let mut game = empty_game();
+        game.soft_drop();
+        game.soft_drop();
+        game.hard_drop();
+        fill_row(&mut game, BOARD_HEIGHT - 5);
+        game.clear_lines();
+
+        let log: Vec<_> = game
+            .score_log
+            .iter()
+            .map(|entry| (entry.piece, entry.reason, entry.points))
+            .collect();
+        assert_eq!(log[0], (1, ScoreReason::SoftDrop, 2));
+        assert_eq!(log[1].0, 1);
+        assert_eq!(log[1].1, ScoreReason::HardDrop);
+        assert_eq!(
+            log[2],
+            (
+                1,
+                ScoreReason::Clear {
+                    lines: 1,
+                    t_spin: None,
+                    back_to_back: false
+                },
+                100
+            )
+        );
+        assert_eq!(log.iter().map(|entry| entry.2).sum::<u32>(), game.score);
+    }
+
+    #[test]
+    fn score_log_keeps_only_the_latest_entries() {
+        let mut game = empty_game();
+        for piece in 0..SCORE_LOG_LEN as u32 + 5 {
+            game.pieces_placed = piece;
+            game.soft_drop();
+            game.current_piece.y = 0;
+        }
+
+        assert_eq!(game.score_log.len(), SCORE_LOG_LEN);
+        assert_eq!(
+            game.score_log.back().unwrap().piece,
+            SCORE_LOG_LEN as u32 + 5
+        );
+        assert_eq!(game.score_log.front().unwrap().piece, 6);
+    }
+
+    #[test]
+    fn blocked_rotation_reports_nothing() {
+        let mut game = empty_game();
+        game.current_piece = Tetromino::new(TetrominoType::O, BOARD_WIDTH);
+        // The O piece turns in place, so it always fits
+        assert_eq!(game.rotate_piece(), vec![GameEvent::Rotated]);
+
+        game.current_piece = Tetromino::new(TetrominoType::I, BOARD_WIDTH);
+        // Nowhere left for the piece to turn into
+        for y in 0..BOARD_HEIGHT {
+            fill_row(&mut game, y);
+        }
+        assert!(game.rotate_piece_ccw().is_empty());
+        assert!(game.rotate_piece_180().is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+//! Controller input, only built with the `gamepad` feature
+
+use gilrs::{Button, EventType, Gilrs};
+use tetris_rs::input::Action;
+
+pub struct Gamepad {
+    /// `None` when the platform has no gamepad support, which makes every poll empty
+    gilrs: Option<Gilrs>,
+}
+
+impl Gamepad {
+    pub fn new() -> Self {
+        Gamepad {
+            gilrs: Gilrs::new().ok(),
+        }
+    }
+
+    /// Actions for the buttons pressed (`true`) or released (`false`) since the last
+    /// call. Controllers connecting or disconnecting mid-game show up as events here
+    /// too and are simply skipped.
+    pub fn button_actions(&mut self) -> Vec<(Action, bool)> {
+        let Some(gilrs) = &mut self.gilrs else {
+            return Vec::new();
+        };
+
+        let mut actions = Vec::new();
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) => {
+                    actions.extend(action_for(button).map(|action| (action, true)));
+                }
+                EventType::ButtonReleased(button, _) => {
+                    actions.extend(action_for(button).map(|action| (action, false)));
+                }
+                _ => {}
+            }
+        }
+        actions
+    }
+
+    /// Left or right and soft drop while held on any connected controller, to keep
+    /// the auto shift and soft drop going
+    pub fn held_actions(&self) -> Vec<Action> {
+        let Some(gilrs) = &self.gilrs else {
+            return Vec::new();
+        };
+        let held = |button| {
+            gilrs
+                .gamepads()
+                .any(|(_, gamepad)| gamepad.is_pressed(button))
+        };
+
+        let mut actions = Vec::new();
+        if held(Button::DPadLeft) {
+            actions.push(Action::MoveLeft);
+        } else if held(Button::DPadRight) {
+            actions.push(Action::MoveRight);
+        }
+        if held(Button::DPadDown) {
+            actions.push(Action::SoftDrop);
+        }
+        actions
+    }
+}
+
+fn action_for(button: Button) -> Option<Action> {
+    match button {
+        Button::DPadLeft => Some(Action::MoveLeft),
+        Button::DPadRight => Some(Action::MoveRight),
+        Button::DPadDown => Some(Action::SoftDrop),
+        Button::DPadUp | Button::LeftTrigger | Button::RightTrigger => Some(Action::HardDrop),
+        Button::South => Some(Action::RotateCW),
+        Button::East => Some(Action::RotateCCW),
+        Button::North => Some(Action::Rotate180),
+        Button::West => Some(Action::Hold),
+        Button::Start => Some(Action::Pause),
+        Button::Select => Some(Action::Restart),
+        _ => None,
+    }
+}
@@ -0,0 +1,663 @@
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A second event for the same key this soon after the first press is taken
+/// as the terminal's own key repeat, meaning the key is being held
+const KEY_REPEAT_WINDOW: Duration = Duration::from_millis(600);
+/// Once repeating, a held key counts as released when its repeats stop for this long
+const RELEASE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Player commands, independent of the key that triggered them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    /// A held left or right moving the piece again under DAS and ARR. Moves the same
+    /// way, but isn't a press of its own, so finesse doesn't count it as an input.
+    /// Never bound to a key.
+    RepeatLeft,
+    RepeatRight,
+    SoftDrop,
+    HardDrop,
+    RotateCW,
+    RotateCCW,
+    Rotate180,
+    Hold,
+    /// Takes back the last lock, in zen mode
+    Undo,
+    Pause,
+    Save,
+    Restart,
+    Quit,
+    /// Shows or hides the frame rate overlay
+    ToggleDebug,
+    /// Shows or hides the checkered grid on empty cells
+    ToggleGrid,
+    /// Hides or shows the side panel, for narrow windows
+    ToggleMinimal,
+    /// Hides or shows the controls help in the side panel
+    ToggleHelp,
+    /// Shows or hides column heights and holes in the side panel
+    ToggleMetrics,
+    /// Flips the board left to right, along with the moves and rotations
+    ToggleMirror,
+}
+
+impl Action {
+    /// What this action does to the game when pressed on a mirrored board. Left and
+    /// right swap, and so do the rotations, since a clockwise turn seen in a mirror
+    /// is a counter-clockwise one.
+    pub fn mirrored(self) -> Action {
+        match self {
+            Action::MoveLeft => Action::MoveRight,
+            Action::MoveRight => Action::MoveLeft,
+            Action::RepeatLeft => Action::RepeatRight,
+            Action::RepeatRight => Action::RepeatLeft,
+            Action::RotateCW => Action::RotateCCW,
+            Action::RotateCCW => Action::RotateCW,
+            other => other,
+        }
+    }
+
+    /// The move a held direction repeats as once DAS runs out
+    pub fn repeated(self) -> Action {
+        match self {
+            Action::MoveLeft => Action::RepeatLeft,
+            Action::MoveRight => Action::RepeatRight,
+            other => other,
+        }
+    }
+}
+
+/// Key names as written in the `[keys]` section of the config file.
+/// Missing entries keep their default binding.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct KeyConfig {
+    pub move_left: Option<String>,
+    pub move_right: Option<String>,
+    pub soft_drop: Option<String>,
+    pub hard_drop: Option<String>,
+    pub rotate_cw: Option<String>,
+    pub rotate_ccw: Option<String>,
+    pub rotate_180: Option<String>,
+    pub hold: Option<String>,
+    pub undo: Option<String>,
+    pub pause: Option<String>,
+    pub save: Option<String>,
+    pub restart: Option<String>,
+    pub quit: Option<String>,
+    pub debug: Option<String>,
+    pub grid: Option<String>,
+    pub minimal: Option<String>,
+    pub help: Option<String>,
+    pub metrics: Option<String>,
+    pub mirror: Option<String>,
+}
+
+/// Timing of held-direction movement and drops, in the `[handling]` section of the config file
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct HandlingConfig {
+    /// Delayed Auto Shift: how long a direction is held before it starts repeating
+    pub das_ms: u64,
+    /// Auto Repeat Rate: time between repeated moves, 0 to shift straight to the wall
+    pub arr_ms: u64,
+    /// How many times faster than gravity a held soft drop falls, 0 to drop to the
+    /// floor at once
+    pub soft_drop_factor: u32,
+    /// Window for the second hard drop press that confirms the first, 0 to hard drop
+    /// on the first press
+    pub confirm_hard_drop_ms: u64,
+}
+
+impl Default for HandlingConfig {
+    fn default() -> Self {
+        HandlingConfig {
+            das_ms: 170,
+            arr_ms: 50,
+            soft_drop_factor: 20,
+            confirm_hard_drop_ms: 0,
+        }
+    }
+}
+
+/// Which action each key sets off. A key does at most one thing, and an action has
+/// at most one key.
+pub struct KeyBindings {
+    actions: HashMap<KeyCode, Action>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            actions: HashMap::from([
+                (KeyCode::Left, Action::MoveLeft),
+                (KeyCode::Right, Action::MoveRight),
+                (KeyCode::Down, Action::SoftDrop),
+                (KeyCode::Char(' '), Action::HardDrop),
+                (KeyCode::Up, Action::RotateCW),
+                (KeyCode::Char('z'), Action::RotateCCW),
+                (KeyCode::Char('x'), Action::Rotate180),
+                (KeyCode::Char('c'), Action::Hold),
+                (KeyCode::Char('u'), Action::Undo),
+                (KeyCode::Char('p'), Action::Pause),
+                (KeyCode::F(5), Action::Save),
+                (KeyCode::Char('r'), Action::Restart),
+                (KeyCode::Char('q'), Action::Quit),
+                (KeyCode::F(3), Action::ToggleDebug),
+                (KeyCode::Char('g'), Action::ToggleGrid),
+                (KeyCode::Char('m'), Action::ToggleMinimal),
+                (KeyCode::F(1), Action::ToggleHelp),
+                (KeyCode::F(4), Action::ToggleMetrics),
+                (KeyCode::F(2), Action::ToggleMirror),
+            ]),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Applies the configured keys on top of the defaults, skipping names that don't
+    /// parse. A configured key is taken away from whichever action had it before,
+    /// leaving that action unbound unless it's given a key of its own.
+    pub fn from_config(config: &KeyConfig) -> Self {
+        let mut bindings = KeyBindings::default();
+        let overrides = [
+            (Action::MoveLeft, &config.move_left),
+            (Action::MoveRight, &config.move_right),
+            (Action::SoftDrop, &config.soft_drop),
+            (Action::HardDrop, &config.hard_drop),
+            (Action::RotateCW, &config.rotate_cw),
+            (Action::RotateCCW, &config.rotate_ccw),
+            (Action::Rotate180, &config.rotate_180),
+            (Action::Hold, &config.hold),
+            (Action::Undo, &config.undo),
+            (Action::Pause, &config.pause),
+            (Action::Save, &config.save),
+            (Action::Restart, &config.restart),
+            (Action::Quit, &config.quit),
+            (Action::ToggleDebug, &config.debug),
+            (Action::ToggleGrid, &config.grid),
+            (Action::ToggleMinimal, &config.minimal),
+            (Action::ToggleHelp, &config.help),
+            (Action::ToggleMetrics, &config.metrics),
+            (Action::ToggleMirror, &config.mirror),
+        ];
+
+        for (action, name) in overrides {
+            if let Some(key) = name.as_deref().and_then(parse_key) {
+                bindings.bind(action, key);
+            }
+        }
+        bindings
+    }
+
+    /// Moves `action` onto `key`, replacing whatever `key` did before
+    fn bind(&mut self, action: Action, key: KeyCode) {
+        self.actions.retain(|_, &mut bound| bound != action);
+        self.actions.insert(key, action);
+    }
+
+    pub fn action_for(&self, key: KeyCode) -> Option<Action> {
+        self.actions.get(&key).copied()
+    }
+
+    /// Display name of the key bound to `action`, for the controls help
+    pub fn key_name(&self, action: Action) -> String {
+        let Some(&key) = self
+            .actions
+            .iter()
+            .find(|(_, &bound)| bound == action)
+            .map(|(key, _)| key)
+        else {
+            return "Unbound".to_string();
+        };
+        match key {
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_uppercase().to_string(),
+            KeyCode::F(n) => format!("F{n}"),
+            other => format!("{other:?}"),
+        }
+    }
+}
+
+/// The soft drop key while it's down, timed separately from the directions so both can
+/// be held at once
+struct HeldSoftDrop {
+    last_seen: Instant,
+    /// When the drops owed so far were counted up to
+    dropped_until: Instant,
+    repeating: bool,
+}
+
+struct HeldKey {
+    action: Action,
+    pressed_at: Instant,
+    last_seen: Instant,
+    /// Whether the terminal has started repeating the key
+    repeating: bool,
+    shifts_done: u32,
+}
+
+/// DAS and ARR for the left/right keys, plus held soft drop. Most terminals only
+/// report presses, so a key counts as held while the terminal keeps sending repeats
+/// of it. Terminals with the kitty keyboard protocol report releases too, and then
+/// a key is held from its press until its release.
+pub struct AutoShift {
+    das: Duration,
+    arr: Duration,
+    soft_drop_factor: u32,
+    held: Option<HeldKey>,
+    soft_drop: Option<HeldSoftDrop>,
+    releases_reported: bool,
+}
+
+impl AutoShift {
+    pub fn new(config: &HandlingConfig) -> Self {
+        AutoShift {
+            das: Duration::from_millis(config.das_ms),
+            arr: Duration::from_millis(config.arr_ms),
+            soft_drop_factor: config.soft_drop_factor,
+            held: None,
+            soft_drop: None,
+            releases_reported: false,
+        }
+    }
+
+    /// Switches to timing held keys by their release events instead of their repeats
+    pub fn use_release_events(&mut self) {
+        self.releases_reported = true;
+    }
+
+    /// Registers a key event for a direction or soft drop. Returns true for a fresh
+    /// press, which should move the piece once; repeats of a held key return false
+    /// since `shifts_due` and `soft_drops_due` take care of those.
+    pub fn key_event(&mut self, action: Action, now: Instant) -> bool {
+        if action == Action::SoftDrop {
+            if let Some(held) = &mut self.soft_drop {
+                if self.releases_reported || now - held.last_seen < KEY_REPEAT_WINDOW {
+                    // Drops start from the first repeat, not the press that already dropped
+                    if !held.repeating {
+                        held.dropped_until = now;
+                    }
+                    held.last_seen = now;
+                    held.repeating = true;
+                    return false;
+                }
+            }
+            self.soft_drop = Some(HeldSoftDrop {
+                last_seen: now,
+                dropped_until: now,
+                repeating: self.releases_reported,
+            });
+            return true;
+        }
+
+        if let Some(held) = &mut self.held {
+            if held.action == action
+                && (self.releases_reported || now - held.last_seen < KEY_REPEAT_WINDOW)
+            {
+                held.last_seen = now;
+                held.repeating = true;
+                return false;
+            }
+        }
+
+        self.held = Some(HeldKey {
+            action,
+            pressed_at: now,
+            last_seen: now,
+            // A reported press is known to be held until its release
+            repeating: self.releases_reported,
+            shifts_done: 0,
+        });
+        true
+    }
+
+    /// Registers the release of a key, ending its auto shift or soft drop at once
+    pub fn key_released(&mut self, action: Action) {
+        if action == Action::SoftDrop {
+            self.soft_drop = None;
+        } else if self.held.as_ref().is_some_and(|held| held.action == action) {
+            self.held = None;
+        }
+    }
+
+    /// How many soft drops a held soft drop key is owed by `now`, after the one
+    /// its press made, falling `soft_drop_factor` times faster than `gravity`. A
+    /// factor of 0 gives `u32::MAX`, meaning all the way down.
+    pub fn soft_drops_due(&mut self, now: Instant, gravity: Duration) -> u32 {
+        let Some(held) = &mut self.soft_drop else {
+            return 0;
+        };
+
+        let released = if self.releases_reported {
+            false
+        } else if held.repeating {
+            now - held.last_seen > RELEASE_TIMEOUT
+        } else {
+            now - held.last_seen > KEY_REPEAT_WINDOW
+        };
+        if released {
+            self.soft_drop = None;
+            return 0;
+        }
+        // Only a tap so far; the drops start once the key is known to be held
+        if !held.repeating {
+            held.dropped_until = now;
+            return 0;
+        }
+        if self.soft_drop_factor == 0 {
+            return u32::MAX;
+        }
+
+        // Counted from the last drop rather than the press, since gravity speeds up
+        let interval = (gravity / self.soft_drop_factor).max(Duration::from_nanos(1));
+        let due = ((now - held.dropped_until).as_nanos() / interval.as_nanos()) as u32;
+        held.dropped_until += interval * due;
+        due
+    }
+
+    /// The held direction and how many moves it's owed by `now`. With an ARR of 0
+    /// this is `u32::MAX`, meaning as far as the piece will go.
+    pub fn shifts_due(&mut self, now: Instant) -> Option<(Action, u32)> {
+        let held = self.held.as_mut()?;
+
+        let released = if self.releases_reported {
+            false
+        } else if held.repeating {
+            now - held.last_seen > RELEASE_TIMEOUT
+        } else {
+            now - held.pressed_at > KEY_REPEAT_WINDOW
+        };
+        if released {
+            self.held = None;
+            return None;
+        }
+
+        let held_for = now - held.pressed_at;
+        if !held.repeating || held_for < self.das {
+            return None;
+        }
+        if self.arr.is_zero() {
+            return Some((held.action, u32::MAX));
+        }
+
+        // One shift when DAS runs out, then one per ARR interval
+        let owed = ((held_for - self.das).as_nanos() / self.arr.as_nanos()) as u32 + 1;
+        let due = owed - held.shifts_done;
+        held.shifts_done = owed;
+        (due > 0).then_some((held.action, due))
+    }
+}
+
+/// Parses a key name such as `"Left"`, `"Space"`, `"F3"` or a single character.
+/// Letters are case-insensitive.
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c.to_ascii_lowercase()));
+    }
+
+    match name.to_ascii_lowercase().as_str() {
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "space" => Some(KeyCode::Char(' ')),
+        "enter" => Some(KeyCode::Enter),
+        "esc" => Some(KeyCode::Esc),
+        "tab" => Some(KeyCode::Tab),
+        "backspace" => Some(KeyCode::Backspace),
+        other => other
+            .strip_prefix('f')
+            .and_then(|n| n.parse().ok())
+            .map(KeyCode::F),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_named_and_character_keys() {
+        assert_eq!(parse_key("Left"), Some(KeyCode::Left));
+        assert_eq!(parse_key("space"), Some(KeyCode::Char(' ')));
+        assert_eq!(parse_key("W"), Some(KeyCode::Char('w')));
+        assert_eq!(parse_key("F3"), Some(KeyCode::F(3)));
+        assert_eq!(parse_key("nonsense"), None);
+    }
+
+    #[test]
+    fn mirroring_swaps_directions_and_rotations_only() {
+        assert_eq!(Action::MoveLeft.mirrored(), Action::MoveRight);
+        assert_eq!(Action::MoveRight.mirrored(), Action::MoveLeft);
+        assert_eq!(Action::RotateCW.mirrored(), Action::RotateCCW);
+        assert_eq!(Action::RotateCCW.mirrored(), Action::RotateCW);
+        for action in [
+            Action::SoftDrop,
+            Action::HardDrop,
+            Action::Rotate180,
+            Action::Hold,
+            Action::ToggleMirror,
+        ] {
+            assert_eq!(action.mirrored(), action);
+        }
+    }
+
+    fn auto_shift() -> AutoShift {
+        AutoShift::new(&HandlingConfig {
+            das_ms: 100,
+            arr_ms: 20,
+            soft_drop_factor: 20,
+            ..HandlingConfig::default()
+        })
+    }
+
+    #[test]
+    fn single_press_moves_once() {
+        let mut shift = auto_shift();
+        let start = Instant::now();
+
+        assert!(shift.key_event(Action::MoveLeft, start));
+        assert_eq!(shift.shifts_due(start + Duration::from_millis(300)), None);
+        assert_eq!(shift.shifts_due(start + KEY_REPEAT_WINDOW * 2), None);
+        // Pressed again well after, it's a new press
+        assert!(shift.key_event(Action::MoveLeft, start + KEY_REPEAT_WINDOW * 2));
+    }
+
+    #[test]
+    fn held_key_repeats_after_das_at_arr() {
+        let mut shift = auto_shift();
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+
+        assert!(shift.key_event(Action::MoveRight, start));
+        // The terminal's own repeats mark the key as held but don't move by themselves
+        assert!(!shift.key_event(Action::MoveRight, ms(50)));
+        assert_eq!(shift.shifts_due(ms(90)), None);
+        assert_eq!(shift.shifts_due(ms(100)), Some((Action::MoveRight, 1)));
+        assert!(!shift.key_event(Action::MoveRight, ms(110)));
+        assert_eq!(shift.shifts_due(ms(145)), Some((Action::MoveRight, 2)));
+        assert_eq!(shift.shifts_due(ms(150)), None);
+
+        // No repeats for a while means the key was let go
+        assert_eq!(shift.shifts_due(ms(110) + RELEASE_TIMEOUT * 2), None);
+        assert!(shift.key_event(Action::MoveRight, ms(500)));
+    }
+
+    #[test]
+    fn zero_arr_shifts_to_the_wall() {
+        let mut shift = AutoShift::new(&HandlingConfig {
+            das_ms: 100,
+            arr_ms: 0,
+            soft_drop_factor: 20,
+            ..HandlingConfig::default()
+        });
+        let start = Instant::now();
+
+        shift.key_event(Action::MoveLeft, start);
+        shift.key_event(Action::MoveLeft, start + Duration::from_millis(50));
+
+        assert_eq!(
+            shift.shifts_due(start + Duration::from_millis(100)),
+            Some((Action::MoveLeft, u32::MAX))
+        );
+    }
+
+    #[test]
+    fn other_direction_takes_over() {
+        let mut shift = auto_shift();
+        let start = Instant::now();
+
+        shift.key_event(Action::MoveLeft, start);
+        assert!(shift.key_event(Action::MoveRight, start + Duration::from_millis(30)));
+        assert!(!shift.key_event(Action::MoveRight, start + Duration::from_millis(60)));
+        assert_eq!(
+            shift.shifts_due(start + Duration::from_millis(130)),
+            Some((Action::MoveRight, 1))
+        );
+    }
+
+    #[test]
+    fn reported_release_ends_the_shift_at_once() {
+        let mut shift = auto_shift();
+        shift.use_release_events();
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+
+        assert!(shift.key_event(Action::MoveLeft, start));
+        // Held without any repeats, well past the usual release timeout
+        assert_eq!(shift.shifts_due(ms(100)), Some((Action::MoveLeft, 1)));
+        assert_eq!(shift.shifts_due(ms(400)), Some((Action::MoveLeft, 15)));
+
+        shift.key_released(Action::MoveRight);
+        assert!(shift.shifts_due(ms(420)).is_some());
+        shift.key_released(Action::MoveLeft);
+        assert_eq!(shift.shifts_due(ms(440)), None);
+        assert!(shift.key_event(Action::MoveLeft, ms(450)));
+    }
+
+    #[test]
+    fn held_soft_drop_falls_at_a_multiple_of_gravity() {
+        let mut shift = auto_shift();
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        // 20 times faster than this gravity is a drop every 25ms
+        let gravity = Duration::from_millis(500);
+
+        // A tap drops once and nothing follows
+        assert!(shift.key_event(Action::SoftDrop, start));
+        assert_eq!(shift.soft_drops_due(ms(200), gravity), 0);
+        assert_eq!(shift.soft_drops_due(ms(700), gravity), 0);
+
+        // Held, the terminal's repeats only keep it going, however fast they come
+        assert!(shift.key_event(Action::SoftDrop, ms(1000)));
+        for repeat in [1300, 1310, 1320, 1330] {
+            assert!(!shift.key_event(Action::SoftDrop, ms(repeat)));
+        }
+        assert_eq!(shift.soft_drops_due(ms(1330), gravity), 1);
+        assert_eq!(shift.soft_drops_due(ms(1410), gravity), 3);
+        // Faster gravity makes for faster soft drops too
+        assert_eq!(shift.soft_drops_due(ms(1420), gravity / 2), 1);
+        assert_eq!(shift.soft_drops_due(ms(1450), gravity / 2), 3);
+
+        // Once the repeats stop the key is let go
+        assert_eq!(
+            shift.soft_drops_due(ms(1330) + RELEASE_TIMEOUT * 2, gravity),
+            0
+        );
+        assert!(shift.key_event(Action::SoftDrop, ms(2000)));
+    }
+
+    #[test]
+    fn reported_soft_drop_is_held_until_released() {
+        let mut shift = auto_shift();
+        shift.use_release_events();
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+        let gravity = Duration::from_millis(500);
+
+        assert!(shift.key_event(Action::SoftDrop, start));
+        assert!(!shift.key_event(Action::SoftDrop, ms(10)));
+        assert_eq!(shift.soft_drops_due(ms(24), gravity), 0);
+        assert_eq!(shift.soft_drops_due(ms(1000), gravity), 40);
+        shift.key_released(Action::SoftDrop);
+        assert_eq!(shift.soft_drops_due(ms(2000), gravity), 0);
+
+        let mut instant = AutoShift::new(&HandlingConfig {
+            soft_drop_factor: 0,
+            ..HandlingConfig::default()
+        });
+        instant.use_release_events();
+        instant.key_event(Action::SoftDrop, start);
+        assert_eq!(instant.soft_drops_due(ms(1), gravity), u32::MAX);
+    }
+
+    #[test]
+    fn config_overrides_only_given_actions() {
+        let config = KeyConfig {
+            move_left: Some("a".to_string()),
+            move_right: Some("d".to_string()),
+            hold: Some("not a key".to_string()),
+            ..KeyConfig::default()
+        };
+
+        let bindings = KeyBindings::from_config(&config);
+
+        assert_eq!(
+            bindings.action_for(KeyCode::Char('a')),
+            Some(Action::MoveLeft)
+        );
+        assert_eq!(
+            bindings.action_for(KeyCode::Char('d')),
+            Some(Action::MoveRight)
+        );
+        assert_eq!(bindings.action_for(KeyCode::Left), None);
+        assert_eq!(bindings.action_for(KeyCode::Char('c')), Some(Action::Hold));
+        assert_eq!(bindings.action_for(KeyCode::Up), Some(Action::RotateCW));
+    }
+
+    #[test]
+    fn wasd_movement_leaves_every_other_default_bound() {
+        let config = KeyConfig {
+            move_left: Some("a".to_string()),
+            move_right: Some("d".to_string()),
+            soft_drop: Some("s".to_string()),
+            rotate_cw: Some("w".to_string()),
+            ..KeyConfig::default()
+        };
+
+        let bindings = KeyBindings::from_config(&config);
+
+        assert_eq!(
+            bindings.action_for(KeyCode::Char('s')),
+            Some(Action::SoftDrop)
+        );
+        assert_eq!(bindings.action_for(KeyCode::F(5)), Some(Action::Save));
+    }
+
+    #[test]
+    fn a_configured_key_takes_over_from_its_default_action() {
+        let config = KeyConfig {
+            hold: Some("Space".to_string()),
+            ..KeyConfig::default()
+        };
+
+        // Checked over many builds, since each one's map may iterate in another order
+        for _ in 0..50 {
+            let bindings = KeyBindings::from_config(&config);
+            assert_eq!(bindings.action_for(KeyCode::Char(' ')), Some(Action::Hold));
+            assert_eq!(bindings.action_for(KeyCode::Char('c')), None);
+            assert_eq!(bindings.key_name(Action::Hold), "Space");
+            assert_eq!(bindings.key_name(Action::HardDrop), "Unbound");
+        }
+    }
+}
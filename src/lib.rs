@@ -0,0 +1,9 @@
+pub mod ai;
+pub mod board;
+pub mod game;
+pub mod input;
+pub mod randomizer;
+pub mod replay;
+mod serde_instant;
+pub mod settings;
+pub mod theme;
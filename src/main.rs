@@ -1,458 +1,2882 @@
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
+    KeyboardEnhancementFlags, MouseButton, MouseEvent, MouseEventKind, PopKeyboardEnhancementFlags,
+    PushKeyboardEnhancementFlags,
+};
+use crossterm::{execute, terminal};
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    widgets::{
-        canvas::{Canvas, Rectangle},
-        Block, Borders, Paragraph,
-    },
-    Frame,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+    DefaultTerminal, Frame,
+};
+use std::env;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tetris_rs::ai::Autoplayer;
+use tetris_rs::board::{BoardMetrics, Cell, BUFFER_ROWS, MAX_BOARD_WIDTH};
+use tetris_rs::game::{
+    save_path, Date, Difficulty, Game, GameConfig, GameEvent, GameMode, GravityMode, GravityTable,
+    PieceSet, ScoreReason, Shape, TSpin, Tetromino, TetrominoType, MAX_HOLD_CAPACITY,
+    MAX_START_LEVEL,
 };
-use std::time::{Duration, Instant};
+use tetris_rs::input::{Action, AutoShift, KeyBindings};
+use tetris_rs::randomizer::Randomizer;
+use tetris_rs::replay::{replay_dir, Playback, Replay};
+use tetris_rs::settings::{first_run, load_high_score, save_high_score, Settings};
+use tetris_rs::theme::Theme;
 
-const BOARD_WIDTH: usize = 10;
-const BOARD_HEIGHT: usize = 20;
-const CELL_CHARS: &str = "    "; // Four spaces for a wider block
-const TICK_RATE: Duration = Duration::from_millis(500);
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod menu;
+#[cfg(feature = "net")]
+mod net;
+#[cfg(feature = "sound")]
+mod sound;
 
-#[derive(Clone, Copy, PartialEq)]
-enum Cell {
-    Empty,
-    Filled(Color),
-}
+use menu::{Menu, MenuChoice};
 
-#[derive(Clone, Copy, PartialEq)]
-enum TetrominoType {
-    I,
-    O,
-    T,
-    L,
-    J,
-    S,
-    Z,
+const CELL_CHARS: &str = "    "; // Four spaces for a wider block, in the side panel
+/// Terminal columns per board cell when drawing blocks, unless configured narrower
+const DEFAULT_CELL_WIDTH: usize = CELL_CHARS.len();
+/// Narrowest blocks can be configured, still two columns to look roughly square
+const MIN_CELL_WIDTH: usize = 2;
+const FEEDBACK_DURATION: Duration = Duration::from_millis(1500);
+/// Latest score log entries listed in the side panel
+const SCORE_LOG_SHOWN: usize = 4;
+/// The stack metrics box: heights, holes and two lines of totals between the borders
+const METRICS_HEIGHT: u16 = 6;
+/// How long the trail behind a hard drop stays visible, a handful of frames
+const TRAIL_DURATION: Duration = Duration::from_millis(100);
+/// How long the level blinks after going up
+const LEVEL_UP_FLASH: Duration = Duration::from_millis(600);
+/// How long the hold box stays lit after a swap, a few frames
+const HOLD_HIGHLIGHT: Duration = Duration::from_millis(100);
+/// Held piece box around the tallest tetromino: two rows of piece with a blank row
+/// either side, plus borders. Taller pentominoes grow it.
+const HOLD_HEIGHT: u16 = 6;
+/// Characters in the side panel's gauge of time left until garbage next rises
+const RISE_GAUGE_WIDTH: usize = 8;
+const DEFAULT_VERTICAL_SCALE: usize = 2; // Each cell is 2 rows high
+/// Time between draws, about 60 FPS, unless `--fps` asks for another rate
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(300);
+/// Delay between autoplayer moves, slow enough to follow along
+const AI_MOVE_INTERVAL: Duration = Duration::from_millis(80);
+/// Block-letter 1, 2 and 3 for the countdown, each character drawn two cells wide
+const BIG_DIGITS: [[&str; 5]; 3] = [
+    [" # ", "## ", " # ", " # ", "###"],
+    ["###", "  #", "###", "#  ", "###"],
+    ["###", "  #", "###", "  #", "###"],
+];
+/// Anything smaller than this in either direction only gets a "resize me" message
+const MIN_AREA_SIZE: u16 = 4;
+
+/// Frame rate measured between draws, for the debug overlay
+#[derive(Default)]
+struct FrameStats {
+    /// Smoothed frames per second, 0 until two frames have been drawn
+    fps: f64,
+    last_frame: Option<Instant>,
 }
 
-impl TetrominoType {
-    fn color(&self) -> Color {
-        match self {
-            TetrominoType::I => Color::Cyan,
-            TetrominoType::O => Color::Yellow,
-            TetrominoType::T => Color::Magenta,
-            TetrominoType::L => Color::White,
-            TetrominoType::J => Color::Blue,
-            TetrominoType::S => Color::Green,
-            TetrominoType::Z => Color::Red,
+impl FrameStats {
+    fn record_frame(&mut self, now: Instant) {
+        if let Some(last) = self.last_frame {
+            let instant_fps = 1.0 / (now - last).as_secs_f64().max(f64::EPSILON);
+            // Averaged over several frames so the number is readable
+            self.fps = if self.fps == 0.0 {
+                instant_fps
+            } else {
+                self.fps * 0.9 + instant_fps * 0.1
+            };
         }
+        self.last_frame = Some(now);
     }
+}
 
-    fn shape(&self) -> Vec<Vec<bool>> {
-        match self {
-            TetrominoType::I => vec![
-                vec![true, true, true, true],
-                vec![false, false, false, false],
-                vec![false, false, false, false],
-                vec![false, false, false, false],
-            ],
-            TetrominoType::O => vec![vec![true, true], vec![true, true]],
-            TetrominoType::T => vec![
-                vec![false, true, false],
-                vec![true, true, true],
-                vec![false, false, false],
-            ],
-            TetrominoType::L => vec![
-                vec![false, false, true],
-                vec![true, true, true],
-                vec![false, false, false],
-            ],
-            TetrominoType::J => vec![
-                vec![true, false, false],
-                vec![true, true, true],
-                vec![false, false, false],
-            ],
-            TetrominoType::S => vec![
-                vec![false, true, true],
-                vec![true, true, false],
-                vec![false, false, false],
-            ],
-            TetrominoType::Z => vec![
-                vec![true, true, false],
-                vec![false, true, true],
-                vec![false, false, false],
-            ],
-        }
-    }
-}
-
-struct Tetromino {
-    piece_type: TetrominoType,
-    shape: Vec<Vec<bool>>,
-    x: i32,
-    y: i32,
-}
-
-impl Tetromino {
-    fn new_random() -> Self {
-        use rand::seq::SliceRandom;
-
-        let piece_types = [
-            TetrominoType::I,
-            TetrominoType::O,
-            TetrominoType::T,
-            TetrominoType::L,
-            TetrominoType::J,
-            TetrominoType::S,
-            TetrominoType::Z,
-        ];
+/// Width of each bar in the latency histogram
+const LATENCY_BUCKET: Duration = Duration::from_millis(4);
+/// Bars in the latency histogram, the last one counting everything slower
+const LATENCY_BUCKETS: usize = 8;
+/// Characters the longest histogram bar fills
+const LATENCY_BAR_WIDTH: usize = 10;
 
-        let piece_type = *piece_types.choose(&mut rand::thread_rng()).unwrap();
-        let shape = piece_type.shape();
-        let width = shape[0].len() as i32;
+/// Time from a key press to the end of the draw after it, gathered with the hidden
+/// `--latency` flag to check how quickly input shows up on screen
+#[derive(Default)]
+struct LatencyStats {
+    /// Oldest press not yet drawn; later presses in the same frame wait less
+    pending: Option<Instant>,
+    /// How many measurements fell into each `LATENCY_BUCKET` wide bar
+    buckets: [u32; LATENCY_BUCKETS],
+    total: Duration,
+    max: Duration,
+}
 
-        Tetromino {
-            piece_type,
-            shape,
-            x: (BOARD_WIDTH as i32 - width) / 2,
-            y: 0,
-        }
+impl LatencyStats {
+    fn key_pressed(&mut self, at: Instant) {
+        self.pending.get_or_insert(at);
     }
 
-    fn rotate_clockwise(&self) -> Vec<Vec<bool>> {
-        let n = self.shape.len();
-        let mut rotated = vec![vec![false; n]; n];
-
-        for i in 0..n {
-            for j in 0..n {
-                rotated[j][n - 1 - i] = self.shape[i][j];
-            }
-        }
+    fn frame_drawn(&mut self, at: Instant) {
+        let Some(pressed) = self.pending.take() else {
+            return;
+        };
+        let latency = at - pressed;
+        let bucket = (latency.as_nanos() / LATENCY_BUCKET.as_nanos()) as usize;
+        self.buckets[bucket.min(LATENCY_BUCKETS - 1)] += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
 
-        rotated
+    fn samples(&self) -> u32 {
+        self.buckets.iter().sum()
     }
 
-    fn color(&self) -> Color {
-        self.piece_type.color()
+    /// Summary and one histogram bar per bucket
+    fn lines(&self) -> Vec<String> {
+        let samples = self.samples();
+        let average = self.total.checked_div(samples).unwrap_or_default();
+        let mut lines = vec![format!(
+            "Latency avg {}ms max {}ms",
+            average.as_millis(),
+            self.max.as_millis()
+        )];
+        let tallest = self.buckets.iter().copied().max().unwrap_or(0).max(1);
+        for (i, &count) in self.buckets.iter().enumerate() {
+            let from = LATENCY_BUCKET.as_millis() * i as u128;
+            let label = if i == LATENCY_BUCKETS - 1 {
+                format!("{from:>3}+ms")
+            } else {
+                format!("{from:>3}ms ")
+            };
+            let bar = "█".repeat(count as usize * LATENCY_BAR_WIDTH / tallest as usize);
+            lines.push(format!("{label} {bar:<LATENCY_BAR_WIDTH$} {count}"));
+        }
+        lines
     }
 }
 
-struct Game {
-    board: Vec<Vec<Cell>>,
-    current_piece: Tetromino,
-    last_tick: Instant,
-    game_over: bool,
-    score: u32,
+/// How board cells are turned into terminal characters
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Renderer {
+    /// Each cell a patch of colored spaces, `width` columns wide and `scale` rows tall
+    Blocks { scale: usize, width: usize },
+    /// Each cell a single colored space, for boards that don't fit as blocks
+    Compact,
+    /// Each cell one column wide and half a row tall: every terminal row shows two
+    /// board rows as an upper half block, colored top by its foreground and bottom
+    /// by its background
+    HalfBlocks,
 }
 
-impl Game {
-    fn rotate_piece(&mut self) {
-        let rotated_shape = self.current_piece.rotate_clockwise();
-
-        // Try normal rotation
-        if self.is_valid_position(&rotated_shape, self.current_piece.x, self.current_piece.y) {
-            self.current_piece.shape = rotated_shape;
-            return;
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::Blocks {
+            scale: DEFAULT_VERTICAL_SCALE,
+            width: DEFAULT_CELL_WIDTH,
         }
+    }
+}
 
-        // Wall kick: try shifting left if rotation fails
-        if self.is_valid_position(
-            &rotated_shape,
-            self.current_piece.x - 1,
-            self.current_piece.y,
-        ) {
-            self.current_piece.shape = rotated_shape;
-            self.current_piece.x -= 1;
-            return;
+/// How the ghost piece shows where the current piece will land
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum GhostStyle {
+    /// Not drawn at all
+    None,
+    /// Filled in a dim color
+    #[default]
+    Dim,
+    /// Hollow boxes on the cells along its edge. The half-block renderer has no room
+    /// for them in its half-row cells, so it fills them dimly instead.
+    Outline,
+}
+
+impl GhostStyle {
+    fn by_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(GhostStyle::None),
+            "dim" => Some(GhostStyle::Dim),
+            "outline" => Some(GhostStyle::Outline),
+            _ => None,
         }
+    }
+}
 
-        // Wall kick: try shifting right if rotation fails
-        if self.is_valid_position(
-            &rotated_shape,
-            self.current_piece.x + 1,
-            self.current_piece.y,
-        ) {
-            self.current_piece.shape = rotated_shape;
-            self.current_piece.x += 1;
-            return;
+impl Renderer {
+    fn by_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "blocks" => Some(Renderer::default()),
+            "compact" => Some(Renderer::Compact),
+            "half-blocks" => Some(Renderer::HalfBlocks),
+            _ => None,
         }
+    }
 
-        // If all attempts fail, the rotation is not performed
+    /// This renderer, or the first smaller one whose full-size board fits in
+    /// `area`, so small panes switch to a compact board instead of cropping it.
+    /// Falls back to the smallest when nothing fits.
+    fn fitting(self, game: &Game, area: Rect) -> Renderer {
+        let blocks = match self {
+            Renderer::Blocks { .. } => self,
+            _ => Renderer::default(),
+        };
+        let by_size = [blocks, Renderer::Compact, Renderer::HalfBlocks];
+        let fits = |renderer: Renderer| {
+            // +2 for borders either way
+            game.board.width() * renderer.cell_width() + 2 <= area.width as usize
+                && renderer.rows_for(game.config.height) + 2 <= area.height as usize
+        };
+        by_size
+            .into_iter()
+            .skip_while(|&renderer| renderer != self)
+            .find(|&renderer| fits(renderer))
+            .unwrap_or(Renderer::HalfBlocks)
     }
 
-    fn new() -> Self {
-        Game {
-            board: vec![vec![Cell::Empty; BOARD_WIDTH]; BOARD_HEIGHT],
-            current_piece: Tetromino::new_random(),
-            last_tick: Instant::now(),
-            game_over: false,
-            score: 0,
+    /// Terminal columns taken by one board cell
+    fn cell_width(self) -> usize {
+        match self {
+            Renderer::Blocks { width, .. } => width,
+            Renderer::Compact | Renderer::HalfBlocks => 1,
         }
     }
 
-    fn clear_lines(&mut self) {
-        let mut lines_cleared = 0;
+    /// The same renderer with blocks `scale` rows tall, if it draws blocks
+    fn with_scale(self, scale: usize) -> Renderer {
+        match self {
+            Renderer::Blocks { width, .. } => Renderer::Blocks {
+                scale: scale.max(1),
+                width,
+            },
+            _ => self,
+        }
+    }
 
-        // Check each line from bottom to top
-        let mut y = BOARD_HEIGHT - 1;
-        while y > 0 {
-            // Check if current line is full
-            if self.board[y]
-                .iter()
-                .all(|cell| matches!(cell, Cell::Filled(_)))
-            {
-                // Move all lines above down by one
-                for row in (1..=y).rev() {
-                    self.board[row] = self.board[row - 1].clone();
-                }
-                // Create new empty line at top
-                self.board[0] = vec![Cell::Empty; BOARD_WIDTH];
-                lines_cleared += 1;
-            } else {
-                y -= 1;
-            }
+    /// The same renderer with blocks `width` columns wide, if it draws blocks
+    fn with_cell_width(self, width: usize) -> Renderer {
+        match self {
+            Renderer::Blocks { scale, .. } => Renderer::Blocks {
+                scale,
+                width: width.clamp(MIN_CELL_WIDTH, DEFAULT_CELL_WIDTH),
+            },
+            _ => self,
         }
+    }
 
-        match lines_cleared {
-            1 => self.score += 100,
-            2 => self.score += 300,
-            3 => self.score += 500,
-            4 => self.score += 800,
-            _ => (),
+    /// Terminal rows taken by `board_rows` rows of the board
+    fn rows_for(self, board_rows: usize) -> usize {
+        match self {
+            Renderer::Blocks { scale, .. } => board_rows * scale,
+            Renderer::Compact => board_rows,
+            Renderer::HalfBlocks => board_rows.div_ceil(2),
         }
     }
 
-    fn tick(&mut self) {
-        if self.game_over {
-            return;
+    /// Board rows, given as cell colors, turned into terminal lines
+    fn lines(self, rows: &[Vec<Color>]) -> Vec<ratatui::text::Line<'static>> {
+        match self {
+            Renderer::Blocks { scale, width } => rows
+                .iter()
+                .flat_map(|row| {
+                    let line = ratatui::text::Line::from(
+                        row.iter()
+                            .map(|&color| {
+                                ratatui::text::Span::styled(
+                                    " ".repeat(width),
+                                    Style::default().bg(color),
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                    );
+                    // Add each row multiple times for vertical scaling
+                    std::iter::repeat_n(line, scale)
+                })
+                .collect(),
+            Renderer::Compact => rows
+                .iter()
+                .map(|row| {
+                    let spans: Vec<_> = row
+                        .iter()
+                        .map(|&color| ratatui::text::Span::styled(" ", Style::default().bg(color)))
+                        .collect();
+                    ratatui::text::Line::from(spans)
+                })
+                .collect(),
+            Renderer::HalfBlocks => half_block_lines(rows, 1),
         }
+    }
 
-        if !self.move_piece(0, 1) {
-            self.freeze_piece();
-            self.clear_lines();
-            self.spawn_new_piece();
+    /// Slices of a board row the piece can be drawn in between rows, each half a
+    /// terminal row tall. The half-block renderer has no slice smaller than a row.
+    fn sub_rows(self) -> usize {
+        match self {
+            Renderer::Blocks { scale, .. } => scale * 2,
+            Renderer::Compact => 2,
+            Renderer::HalfBlocks => 1,
         }
     }
+}
 
-    fn move_piece(&mut self, dx: i32, dy: i32) -> bool {
-        let new_x = self.current_piece.x + dx;
-        let new_y = self.current_piece.y + dy;
+/// Rows of colors turned into terminal lines two at a time, as upper half blocks
+/// colored top by their foreground and bottom by their background, each color
+/// `width` columns wide
+fn half_block_lines(rows: &[Vec<Color>], width: usize) -> Vec<ratatui::text::Line<'static>> {
+    rows.chunks(2)
+        .map(|pair| {
+            let spans: Vec<_> = (0..pair[0].len())
+                .map(|x| {
+                    // An odd last row leaves the lower half to the terminal
+                    let top = pair[0][x];
+                    let bottom = pair.get(1).map_or(Color::Reset, |row| row[x]);
+                    // A foreground of `Reset` is the terminal's text color rather
+                    // than its background, so a cell left to the terminal has to
+                    // be the background half
+                    match (top, bottom) {
+                        (Color::Reset, Color::Reset) => {
+                            ratatui::text::Span::styled(" ".repeat(width), Style::default().bg(top))
+                        }
+                        (Color::Reset, _) => ratatui::text::Span::styled(
+                            "▄".repeat(width),
+                            Style::default().fg(bottom).bg(top),
+                        ),
+                        _ => ratatui::text::Span::styled(
+                            "▀".repeat(width),
+                            Style::default().fg(top).bg(bottom),
+                        ),
+                    }
+                })
+                .collect();
+            ratatui::text::Line::from(spans)
+        })
+        .collect()
+}
 
-        if self.is_valid_position(&self.current_piece.shape, new_x, new_y) {
-            self.current_piece.x = new_x;
-            self.current_piece.y = new_y;
-            true
+fn main() {
+    let settings = Settings::load();
+    let bindings = KeyBindings::from_config(&settings.keys);
+    // The computer never misdrops, and confirming would only slow it down
+    let ai = env::args().any(|arg| arg == "--ai");
+    let confirm_hard_drop_ms = settings.handling.confirm_hard_drop_ms;
+    let config = GameConfig {
+        confirm_hard_drop: (confirm_hard_drop_ms > 0 && !ai)
+            .then(|| Duration::from_millis(confirm_hard_drop_ms)),
+        ..parse_config()
+    };
+    let mut menu = Menu::new(&config, arg_value("--theme").or(settings.theme).as_deref());
+    let board_theme = |theme: Theme| {
+        if settings.transparent_board {
+            theme.transparent()
         } else {
-            false
+            theme
         }
+    };
+    let mut theme = board_theme(menu.theme());
+    let renderer = arg_value("--renderer")
+        .or(settings.renderer)
+        .and_then(|name| Renderer::by_name(&name))
+        .unwrap_or_default();
+    let renderer = arg_value("--scale")
+        .and_then(|value| value.parse().ok())
+        .or(settings.vertical_scale)
+        .map_or(renderer, |scale| renderer.with_scale(scale));
+    let renderer = arg_value("--cell-width")
+        .and_then(|value| value.parse().ok())
+        .or(settings.cell_width)
+        .map_or(renderer, |width| renderer.with_cell_width(width));
+    // Input is still read as it arrives between frames, and the simulation runs on
+    // real time, so this only limits how often the screen is redrawn
+    let frame_interval = arg_value("--fps")
+        .and_then(|value| value.parse().ok())
+        .filter(|&fps: &u32| fps > 0)
+        .map_or(FRAME_INTERVAL, |fps| Duration::from_secs(1) / fps);
+    let mut playback = arg_value("--replay").map(|path| {
+        let replay = Replay::load_from(Path::new(&path))
+            .unwrap_or_else(|err| exit_with_error(&format!("failed to load replay {path}: {err}")));
+        Playback::new(replay)
+    });
+
+    // Waiting for the other player happens before taking over the screen, so the
+    // waiting message stays readable
+    #[cfg(feature = "net")]
+    let mut versus = connect_versus();
+    #[cfg(feature = "net")]
+    let spectator = arg_value("--spectate").map(|address| {
+        net::Spectator::connect(&address)
+            .unwrap_or_else(|err| exit_with_error(&format!("failed to spectate {address}: {err}")))
+    });
+    #[cfg(feature = "net")]
+    let mut broadcast = env::args().any(|arg| arg == "--broadcast").then(|| {
+        net::Broadcast::start(net::SPECTATE_PORT)
+            .unwrap_or_else(|err| exit_with_error(&format!("failed to start broadcasting: {err}")))
+    });
+    // Opened before taking over the screen, since audio backends may print warnings
+    #[cfg(feature = "sound")]
+    let sound = sound::Sound::new();
+    let mut terminal = ratatui::init();
+    execute!(io::stdout(), EnableMouseCapture).expect("failed to enable mouse capture");
+    // Terminals with the kitty keyboard protocol can report key repeats and releases
+    let keyboard_enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false)
+        && execute!(
+            io::stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )
+        .is_ok();
+    // ratatui's own hook, installed by `init`, leaves raw mode and the alternate
+    // screen before the default handler prints, but doesn't know about mouse
+    // reporting or the keyboard flags. Chaining onto it undoes those first, so a
+    // crash mid-game hands back a usable shell with the panic message on it.
+    let panic_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_input_modes(keyboard_enhanced);
+        panic_hook(info);
+    }));
+
+    #[cfg(feature = "net")]
+    if let Some(spectator) = spectator {
+        spectate(
+            &mut terminal,
+            spectator,
+            &bindings,
+            &theme,
+            renderer,
+            settings.grid,
+        );
+        restore_input_modes(keyboard_enhanced);
+        ratatui::restore();
+        return;
     }
 
-    // Update is_valid_position to check for Cell::Empty
-    fn is_valid_position(&self, shape: &Vec<Vec<bool>>, x: i32, y: i32) -> bool {
-        for (row_idx, row) in shape.iter().enumerate() {
-            for (col_idx, &is_filled) in row.iter().enumerate() {
-                if is_filled {
-                    let board_x = x + col_idx as i32;
-                    let board_y = y + row_idx as i32;
+    // Only fresh games are recorded, since a replay has to start from the first piece
+    let (mut game, mut recording) = match &playback {
+        Some(playback) => (playback.game(), None),
+        None => match load_resumable_game(&mut terminal) {
+            Some(game) => (game, None),
+            None => {
+                let Some((game, recording)) = start_from_menu(&mut terminal, &mut menu, &config)
+                else {
+                    restore_input_modes(keyboard_enhanced);
+                    ratatui::restore();
+                    return;
+                };
+                theme = board_theme(menu.theme());
+                (game, Some(recording))
+            }
+        },
+    };
+    // A resumed game may have been beaten by another since it was saved
+    game.high_score = game.high_score.max(load_high_score());
+    let mut autoplayer = (playback.is_none() && ai).then(Autoplayer::default);
+    let mut last_ai_move = Instant::now();
+
+    let mut last_update = Instant::now();
+    let mut last_draw: Option<Instant> = None;
+    let mut screen = Rect::default();
+    let mut last_click: Option<Instant> = None;
+    let mut notice: Option<(&str, Instant)> = None;
+    let mut end_handled = false;
+    // Set when a replay ends somewhere other than where its recording did
+    let mut replay_mismatch = false;
+    let mut auto_shift = AutoShift::new(&settings.handling);
+    if keyboard_enhanced {
+        auto_shift.use_release_events();
+    }
+    let mut frame_stats = FrameStats::default();
+    let mut latency = env::args()
+        .any(|arg| arg == "--latency")
+        .then(LatencyStats::default);
+    let mut show_debug = false;
+    let mut view = View {
+        grid: settings.grid,
+        minimal: settings.minimal,
+        help: settings.help.unwrap_or_else(first_run),
+        metrics: false,
+        mirror: settings.mirror,
+        ghost: arg_value("--ghost")
+            .or(settings.ghost)
+            .and_then(|name| GhostStyle::by_name(&name))
+            .unwrap_or_default(),
+        smooth: settings.smooth_falling || env::args().any(|arg| arg == "--smooth"),
+    };
+    #[cfg(feature = "gamepad")]
+    let mut gamepad = gamepad::Gamepad::new();
+
+    'game: loop {
+        // Feed real elapsed time to the simulation so gravity stays steady even
+        // when drawing is slow. A replay steps the game itself, to hand over each
+        // action on the step it was recorded on.
+        let now = Instant::now();
+        let mut events = match &mut playback {
+            Some(playback) => playback.advance(&mut game, now - last_update),
+            None => game.update(now - last_update),
+        };
+        last_update = now;
+
+        // A finished game has nothing left to resume, but is worth keeping as a replay.
+        // Waiting out the top-out animation leaves the board the same whenever the
+        // frames fell, so the replay's hash can be checked against it.
+        if game.is_over() && !game.is_topping_out() && !end_handled {
+            if playback.is_none() {
+                remove_resume_file();
+                save_high_score(game.high_score);
+            }
+            // Garbage from the opponent isn't in the recorded actions, so the replay
+            // wouldn't play back the same
+            #[cfg(feature = "net")]
+            if versus.is_some() {
+                recording = None;
+            }
+            if let Some(recording) = &mut recording {
+                recording.finish(&game);
+                save_replay(recording);
+            }
+            replay_mismatch = playback
+                .as_ref()
+                .and_then(|playback| playback.matches(&game))
+                == Some(false);
+            end_handled = true;
+        } else if !game.is_over() {
+            end_handled = false;
+        }
+
+        #[cfg(feature = "gamepad")]
+        if playback.is_none() {
+            for (action, pressed) in gamepad.button_actions() {
+                let action = view.oriented(action);
+                if pressed {
+                    events.extend(press(
+                        &mut game,
+                        recording.as_mut(),
+                        &mut auto_shift,
+                        action,
+                    ));
+                } else {
+                    auto_shift.key_released(action);
+                }
+            }
+            // A held button sends no repeats like a terminal key, so stand in for them
+            for action in gamepad.held_actions() {
+                auto_shift.key_event(view.oriented(action), Instant::now());
+            }
+        }
 
-                    if board_x < 0
-                        || board_x >= BOARD_WIDTH as i32
-                        || board_y >= BOARD_HEIGHT as i32
-                    {
-                        return false;
+        if let Some((action, shifts)) = auto_shift.shifts_due(Instant::now()) {
+            // Capped at the board width since an ARR of 0 asks for "all the way".
+            // Recorded as repeats, so finesse counts the hold as the one press.
+            for _ in 0..shifts.min(game.board.width() as u32) {
+                events.extend(apply_action(
+                    &mut game,
+                    recording.as_mut(),
+                    action.repeated(),
+                ));
+            }
+        }
+        let soft_drops = auto_shift.soft_drops_due(Instant::now(), game.tick_rate());
+        for _ in 0..soft_drops.min(game.board.height() as u32) {
+            events.extend(apply_action(
+                &mut game,
+                recording.as_mut(),
+                Action::SoftDrop,
+            ));
+        }
+
+        if let Some(autoplayer) = &mut autoplayer {
+            if last_ai_move.elapsed() >= AI_MOVE_INTERVAL {
+                if let Some(action) = autoplayer.next_action(&game) {
+                    events.extend(apply_action(&mut game, recording.as_mut(), action));
+                }
+                last_ai_move = Instant::now();
+            }
+        }
+
+        #[cfg(feature = "net")]
+        if let Some(versus) = &mut versus {
+            for event in versus.receive() {
+                match event {
+                    GameEvent::GarbageSent(lines) => {
+                        let gap = rand::random::<usize>() % game.board.width();
+                        game.add_garbage(lines as usize, gap);
                     }
+                    // Outlasting the opponent wins
+                    GameEvent::GameOver => game.win(),
+                    _ => {}
+                }
+            }
+        }
 
-                    if board_y >= 0 {
-                        match self.board[board_y as usize][board_x as usize] {
-                            Cell::Empty => {}
-                            Cell::Filled(_) => return false,
-                        }
+        if last_draw.is_none_or(|at| at.elapsed() >= frame_interval) {
+            #[cfg(feature = "net")]
+            let versus_status = versus.as_ref().map(net::Versus::status).unwrap_or_default();
+            let mut status = Vec::new();
+            if playback.is_some() {
+                status.push("REPLAY");
+            }
+            if replay_mismatch {
+                status.push("REPLAY MISMATCH");
+            }
+            if let Some((text, at)) = notice {
+                if at.elapsed() < FEEDBACK_DURATION {
+                    status.push(text);
+                }
+            }
+            #[cfg(feature = "net")]
+            status.extend(versus_status.iter().map(String::as_str));
+            screen = terminal
+                .draw(|f| {
+                    draw(f, &game, &bindings, &theme, renderer, view, &status);
+                    if show_debug {
+                        draw_debug_overlay(f, &game, renderer, view.minimal, &frame_stats);
+                    }
+                    if let Some(latency) = &latency {
+                        draw_latency_overlay(f, latency);
                     }
+                })
+                .expect("failed to draw frame")
+                .area;
+            let drawn_at = Instant::now();
+            frame_stats.record_frame(drawn_at);
+            if let Some(latency) = &mut latency {
+                latency.frame_drawn(drawn_at);
+            }
+            last_draw = Some(drawn_at);
+        }
+
+        // Wait for input until the next frame is due, then take everything else already
+        // queued, so a move and a rotation pressed together land in the same frame
+        let mut timeout = last_draw.map_or(Duration::ZERO, |at| {
+            frame_interval.saturating_sub(at.elapsed())
+        });
+        while event::poll(timeout).unwrap() {
+            timeout = Duration::ZERO;
+            let event = event::read().unwrap();
+            if let (Some(latency), Event::Key(key)) = (&mut latency, &event) {
+                if key.kind == KeyEventKind::Press {
+                    latency.key_pressed(Instant::now());
                 }
             }
+            match event {
+                // Releases end a held move or soft drop, and repeats are covered by
+                // timing the hold, so neither counts as a press
+                Event::Key(key) if key.kind == KeyEventKind::Release => {
+                    if let Some(action) = bindings.action_for(key.code) {
+                        auto_shift.key_released(view.oriented(action));
+                    }
+                }
+                Event::Key(key) if key.kind == KeyEventKind::Repeat => {}
+                // Watching a replay, the only thing left to do is stop watching
+                Event::Key(key)
+                    if playback.is_some()
+                        && bindings.action_for(key.code) == Some(Action::Quit) =>
+                {
+                    break 'game
+                }
+                Event::Key(_) if playback.is_some() => {}
+                // The board filling up after a top out gives way to any key
+                Event::Key(_) if game.is_topping_out() => game.skip_top_out(),
+                // Once it's over, anything but a restart goes back to the menu
+                Event::Key(key)
+                    if game.is_over() && bindings.action_for(key.code) != Some(Action::Restart) =>
+                {
+                    let Some((mut next, next_recording)) =
+                        start_from_menu(&mut terminal, &mut menu, &config)
+                    else {
+                        break 'game;
+                    };
+                    next.high_score = game.high_score;
+                    (game, recording) = (next, Some(next_recording));
+                    theme = board_theme(menu.theme());
+                    auto_shift = AutoShift::new(&settings.handling);
+                    if keyboard_enhanced {
+                        auto_shift.use_release_events();
+                    }
+                    last_update = Instant::now();
+                    last_draw = None;
+                }
+                Event::Key(key) => match bindings
+                    .action_for(key.code)
+                    .map(|action| view.oriented(action))
+                {
+                    Some(Action::Quit) => {
+                        // Keep the game in progress for next time
+                        if !game.is_over() {
+                            if let Some(path) = save_path() {
+                                let _ = game.save_to(&path);
+                            }
+                        }
+                        break 'game;
+                    }
+                    Some(Action::Save) => {
+                        let saved = save_path()
+                            .ok_or_else(|| io::Error::other("no config directory"))
+                            .and_then(|path| game.save_to(&path));
+                        let text = if saved.is_ok() {
+                            "Game saved"
+                        } else {
+                            "Save failed"
+                        };
+                        notice = Some((text, Instant::now()));
+                    }
+                    Some(Action::ToggleDebug) => show_debug = !show_debug,
+                    Some(Action::ToggleGrid) => view.grid = !view.grid,
+                    Some(Action::ToggleMinimal) => view.minimal = !view.minimal,
+                    Some(Action::ToggleHelp) => view.help = !view.help,
+                    Some(Action::ToggleMetrics) => view.metrics = !view.metrics,
+                    Some(Action::ToggleMirror) => view.mirror = !view.mirror,
+                    Some(action) => events.extend(press(
+                        &mut game,
+                        recording.as_mut(),
+                        &mut auto_shift,
+                        action,
+                    )),
+                    None => {}
+                },
+                // Redraw straight away so the old layout doesn't linger garbled
+                Event::Resize(_, _) => last_draw = None,
+                Event::Mouse(mouse) if playback.is_none() => events.extend(handle_mouse(
+                    &mut game,
+                    recording.as_mut(),
+                    mouse,
+                    screen,
+                    renderer,
+                    view,
+                    &mut last_click,
+                )),
+                _ => {}
+            }
+        }
+
+        if events
+            .iter()
+            .any(|event| matches!(event, GameEvent::LevelUp(_)))
+        {
+            notice = Some(("Level up!", Instant::now()));
         }
-        true
+        #[cfg(feature = "net")]
+        if let Some(versus) = &mut versus {
+            versus.send(&events);
+        }
+        #[cfg(feature = "net")]
+        if let Some(broadcast) = &mut broadcast {
+            broadcast.send(&game);
+        }
+        #[cfg(feature = "sound")]
+        for event in events {
+            sound.play(event);
+        }
+    }
+    // A replay's score was already counted when it was played
+    if playback.is_none() {
+        save_high_score(game.high_score);
     }
+    restore_input_modes(keyboard_enhanced);
+    ratatui::restore()
+}
 
-    fn freeze_piece(&mut self) {
-        let color = self.current_piece.color();
-        for (row_idx, row) in self.current_piece.shape.iter().enumerate() {
-            for (col_idx, &is_filled) in row.iter().enumerate() {
-                if is_filled {
-                    let board_x = self.current_piece.x + col_idx as i32;
-                    let board_y = self.current_piece.y + row_idx as i32;
-                    if board_y >= 0 && board_y < BOARD_HEIGHT as i32 {
-                        self.board[board_y as usize][board_x as usize] = Cell::Filled(color);
-                    }
+/// Reports a problem with the arguments or files given at startup and quits. Only
+/// for before the terminal is taken over, so the message stays readable.
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("tetris-rs: {message}");
+    std::process::exit(1)
+}
+
+/// Turns off the mouse reporting and keyboard enhancements turned on at startup,
+/// which ratatui doesn't know to undo
+fn restore_input_modes(keyboard_enhanced: bool) {
+    if keyboard_enhanced {
+        let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+    }
+    let _ = execute!(io::stdout(), DisableMouseCapture);
+}
+
+/// Hosts with `--host`, on `--port` if given, or joins the host at `--connect <addr>`.
+/// Without either it's a solo game.
+#[cfg(feature = "net")]
+fn connect_versus() -> Option<net::Versus> {
+    let versus = if env::args().any(|arg| arg == "--host") {
+        let port = arg_value("--port")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(net::DEFAULT_PORT);
+        println!("Waiting for an opponent on port {port}...");
+        net::Versus::host(port)
+    } else {
+        let address = arg_value("--connect")?;
+        net::Versus::connect(&address)
+    };
+    Some(
+        versus
+            .unwrap_or_else(|err| exit_with_error(&format!("failed to start versus game: {err}"))),
+    )
+}
+
+/// Shows the game broadcast by another player until the viewer quits. Every other
+/// key is ignored, since there's nothing to control.
+#[cfg(feature = "net")]
+fn spectate(
+    terminal: &mut DefaultTerminal,
+    mut spectator: net::Spectator,
+    bindings: &KeyBindings,
+    theme: &Theme,
+    renderer: Renderer,
+    grid: bool,
+) {
+    let view = View {
+        grid,
+        ..View::default()
+    };
+    // Nothing to show until the first snapshot arrives
+    let mut game: Option<Game> = None;
+    loop {
+        if let Some(snapshot) = spectator.latest() {
+            game.get_or_insert_with(|| Game::new(Difficulty::Normal))
+                .show_snapshot(&snapshot);
+        }
+        let status: &[&str] = if spectator.ended {
+            &["SPECTATING", "Broadcast ended"]
+        } else {
+            &["SPECTATING"]
+        };
+        terminal
+            .draw(|f| match &game {
+                Some(game) => draw(f, game, bindings, theme, renderer, view, status),
+                None => {
+                    let waiting = Paragraph::new(if spectator.ended {
+                        "Broadcast ended"
+                    } else {
+                        "Waiting for the game..."
+                    })
+                    .alignment(Alignment::Center)
+                    .block(Block::default().borders(Borders::ALL));
+                    f.render_widget(waiting, centered_rect(f.area(), 28, 3));
+                }
+            })
+            .expect("failed to draw frame");
+
+        if event::poll(FRAME_INTERVAL).unwrap() {
+            if let Event::Key(key) = event::read().unwrap() {
+                if key.kind == KeyEventKind::Press
+                    && bindings.action_for(key.code) == Some(Action::Quit)
+                {
+                    return;
                 }
             }
         }
     }
+}
+
+/// Value following `name` on the command line, like the path in `--replay <path>`
+fn arg_value(name: &str) -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+    args.windows(2)
+        .find(|pair| pair[0] == name)
+        .map(|pair| pair[1].clone())
+}
 
-    fn spawn_new_piece(&mut self) {
-        self.current_piece = Tetromino::new_random();
+/// Handles a pressed key or button. Repeats of a held direction are left to the auto
+/// shift, everything else is applied straight away.
+fn press(
+    game: &mut Game,
+    recording: Option<&mut Replay>,
+    auto_shift: &mut AutoShift,
+    action: Action,
+) -> Vec<GameEvent> {
+    if matches!(
+        action,
+        Action::MoveLeft | Action::MoveRight | Action::SoftDrop
+    ) && !auto_shift.key_event(action, Instant::now())
+    {
+        return Vec::new();
+    }
+    apply_action(game, recording, action)
+}
 
-        // Check if the new piece can be placed at spawn position
-        if !self.is_valid_position(
-            &self.current_piece.shape,
-            self.current_piece.x,
-            self.current_piece.y,
-        ) {
-            self.game_over = true;
+/// Applies `action` and adds it to the recording at the current game time. A
+/// restart begins a new game, so the recording starts over with it.
+fn apply_action(game: &mut Game, recording: Option<&mut Replay>, action: Action) -> Vec<GameEvent> {
+    let at = game.elapsed();
+    let events = game.handle_action(action);
+    if let Some(recording) = recording {
+        if action == Action::Restart {
+            *recording = Replay::new(game);
+        } else {
+            recording.record(at, action);
         }
     }
+    events
 }
 
-fn main() {
-    let mut terminal = ratatui::init();
-    let mut game = Game::new();
+/// Writes a finished game's replay, named after the time it was saved
+fn save_replay(recording: &Replay) {
+    let Some(dir) = replay_dir() else {
+        return;
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    // Losing a replay isn't worth interrupting the game over
+    let _ = recording.save_to(&dir.join(format!("replay-{timestamp}.json")));
+}
 
+/// Looks for a game saved on the last quit and, unless `--resume` was passed, asks
+/// whether to continue it. Declining discards the save.
+fn load_resumable_game(terminal: &mut DefaultTerminal) -> Option<Game> {
+    let mut game = Game::load_from(&save_path()?).ok()?;
+    if !env::args().any(|arg| arg == "--resume") && !prompt_resume(terminal) {
+        remove_resume_file();
+        return None;
+    }
+
+    // Come back paused so the player has a moment to get their bearings
+    if !game.paused {
+        game.toggle_pause();
+    }
+    Some(game)
+}
+
+/// Shows the resume question until the player answers it
+fn prompt_resume(terminal: &mut DefaultTerminal) -> bool {
     loop {
         terminal
-            .draw(|f| draw(f, &game))
+            .draw(|f| {
+                let lines = vec![
+                    ratatui::text::Line::styled(
+                        "Saved game found",
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    ratatui::text::Line::from(""),
+                    ratatui::text::Line::from("Continue? (Y/N)"),
+                ];
+                let prompt = Paragraph::new(lines)
+                    .alignment(Alignment::Center)
+                    .block(Block::default().borders(Borders::ALL));
+                f.render_widget(prompt, centered_rect(f.area(), 24, 5));
+            })
             .expect("failed to draw frame");
 
-        if game.last_tick.elapsed() >= TICK_RATE {
-            game.tick();
-            game.last_tick = Instant::now();
+        // Only presses, so a key released on a terminal reporting releases isn't read twice
+        if let Event::Key(key) = event::read().unwrap() {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => return true,
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => return false,
+                _ => {}
+            }
         }
+    }
+}
 
-        if event::poll(Duration::from_millis(50)).unwrap() {
-            if let Event::Key(key) = event::read().unwrap() {
-                match key.code {
-                    KeyCode::Char('q') => break,
-                    KeyCode::Left => {
-                        game.move_piece(-1, 0);
-                    }
-                    KeyCode::Right => {
-                        game.move_piece(1, 0);
-                    }
-                    KeyCode::Down => {
-                        game.move_piece(0, 1);
-                    }
-                    KeyCode::Up => {
-                        game.rotate_piece();
-                    }
-                    _ => {}
+/// Shows the menu until the player starts a game, which is built from `base` with
+/// the menu's choices and recorded from its first piece, or quits
+fn start_from_menu(
+    terminal: &mut DefaultTerminal,
+    menu: &mut Menu,
+    base: &GameConfig,
+) -> Option<(Game, Replay)> {
+    loop {
+        terminal
+            .draw(|f| menu.draw(f, f.area()))
+            .expect("failed to draw frame");
+
+        // Only presses, so a key released on a terminal reporting releases isn't read twice
+        if let Event::Key(key) = event::read().unwrap() {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match menu.handle_key(key.code) {
+                Some(MenuChoice::Start) => {
+                    let game = Game::with_config(menu.config(base));
+                    let recording = Replay::new(&game);
+                    return Some((game, recording));
                 }
+                Some(MenuChoice::Quit) => return None,
+                None => {}
             }
         }
     }
-    ratatui::restore()
 }
 
-fn draw(frame: &mut Frame, game: &Game) {
-    // Create the main layout
-    let chunks = Layout::default()
+fn remove_resume_file() {
+    if let Some(path) = save_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Left click moves the piece to the clicked column and drops it a cell, or hard
+/// drops on a double click. Right click rotates.
+fn handle_mouse(
+    game: &mut Game,
+    mut recording: Option<&mut Replay>,
+    mouse: MouseEvent,
+    screen: Rect,
+    renderer: Renderer,
+    view: View,
+    last_click: &mut Option<Instant>,
+) -> Vec<GameEvent> {
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            let board_area = main_chunks(screen, view.minimal)[0];
+            let renderer = renderer.fitting(game, board_area);
+            let board = board_rect(game, renderer, board_area);
+            if mouse.column < board.x || mouse.column >= board.x + board.width {
+                return Vec::new();
+            }
+            let column = (mouse.column - board.x) as usize / renderer.cell_width();
+            let column = if view.mirror {
+                (game.board.width() - 1).saturating_sub(column)
+            } else {
+                column
+            };
+            let (at, start_x) = (game.elapsed(), game.current_piece.x);
+            game.move_to_column(column as i32);
+
+            // Recorded as the single steps it took, so playback needs no mouse
+            if let Some(recording) = recording.as_deref_mut() {
+                let dx = game.current_piece.x - start_x;
+                let step = if dx < 0 {
+                    Action::MoveLeft
+                } else {
+                    Action::MoveRight
+                };
+                for _ in 0..dx.abs() {
+                    recording.record(at, step);
+                }
+            }
+
+            let drop = if last_click.is_some_and(|at| at.elapsed() < DOUBLE_CLICK_INTERVAL) {
+                *last_click = None;
+                Action::HardDrop
+            } else {
+                *last_click = Some(Instant::now());
+                Action::SoftDrop
+            };
+            apply_action(game, recording, drop)
+        }
+        MouseEventKind::Down(MouseButton::Right) => {
+            apply_action(game, recording, view.oriented(Action::RotateCW))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Builds the game config from the `--mode`, `--gravity`, `--seed`, `--width`, `--height`,
+/// `--preview`, `--goal`, `--garbage`, `--start-level`, `--speed`, `--lock-resets`, `--pieces`,
+/// `--randomizer`, `--hold` and `--no-top-out`
+/// arguments, falling back to the `--difficulty` preset for anything missing or unparsable
+fn parse_config() -> GameConfig {
+    let difficulty = match arg_value("--difficulty").as_deref() {
+        Some("easy") => Difficulty::Easy,
+        Some("hard") => Difficulty::Hard,
+        _ => Difficulty::Normal,
+    };
+    let defaults = GameConfig::with_difficulty(difficulty);
+    let piece_set = match arg_value("--pieces").as_deref() {
+        Some("pentominoes") => PieceSet::Pentominoes,
+        _ => defaults.piece_set,
+    };
+    let min_size = piece_set.max_shape_size();
+    let height = arg_value("--height")
+        .and_then(|value| value.parse().ok())
+        .map_or(defaults.height, |height: usize| height.max(min_size));
+    let daily = env::args().any(|arg| arg == "--daily").then(Date::today);
+    GameConfig {
+        mode: match arg_value("--mode").as_deref() {
+            Some("sprint") => GameMode::Sprint,
+            Some("dig") => GameMode::Dig,
+            Some("survival") => GameMode::Survival,
+            Some("zen") => GameMode::Zen,
+            _ => defaults.mode,
+        },
+        gravity: match arg_value("--gravity").as_deref() {
+            Some("cascade") => GravityMode::Cascade,
+            _ => defaults.gravity,
+        },
+        // Narrower or shorter boards can't fit the longest piece, and wider rows
+        // don't fit the board's occupancy bits
+        width: arg_value("--width")
+            .and_then(|value| value.parse().ok())
+            .map_or(defaults.width, |width: usize| {
+                width.clamp(min_size, MAX_BOARD_WIDTH)
+            }),
+        height,
+        seed: daily
+            .map(|date| date.seed())
+            .or_else(|| arg_value("--seed").and_then(|value| value.parse().ok())),
+        daily,
+        preview_count: arg_value("--preview")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(defaults.preview_count),
+        // 0 plays an endless marathon
+        marathon_goal: arg_value("--goal")
+            .and_then(|value| value.parse().ok())
+            .map_or(defaults.marathon_goal, |goal: u32| {
+                (goal > 0).then_some(goal)
+            }),
+        // Leaves room above the garbage for the longest piece to spawn
+        garbage_rows: arg_value("--garbage")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(defaults.garbage_rows)
+            .min(height - min_size),
+        start_level: arg_value("--start-level")
+            .and_then(|value| value.parse().ok())
+            .map_or(defaults.start_level, |level: u32| {
+                level.min(MAX_START_LEVEL)
+            }),
+        gravity_table: match arg_value("--speed").as_deref() {
+            Some("nes") => GravityTable::Nes,
+            Some("guideline") => GravityTable::Guideline,
+            _ => defaults.gravity_table,
+        },
+        max_lock_resets: arg_value("--lock-resets")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(defaults.max_lock_resets),
+        top_out_animation: !env::args().any(|arg| arg == "--no-top-out"),
+        finesse: env::args().any(|arg| arg == "--finesse"),
+        hold_capacity: arg_value("--hold")
+            .and_then(|value| value.parse().ok())
+            .map_or(defaults.hold_capacity, |count: usize| {
+                count.clamp(1, MAX_HOLD_CAPACITY)
+            }),
+        piece_set,
+        randomizer: arg_value("--randomizer")
+            .and_then(|name| Randomizer::by_name(&name))
+            .unwrap_or(defaults.randomizer),
+        ..defaults
+    }
+}
+
+/// Display toggles the player can flip mid-game
+#[derive(Clone, Copy, Debug, Default)]
+struct View {
+    /// Checkered grid on empty cells
+    grid: bool,
+    /// Side panel hidden, giving the board the whole width with the score laid over it
+    minimal: bool,
+    /// Controls help at the bottom of the side panel
+    help: bool,
+    /// Column heights and holes in the side panel
+    metrics: bool,
+    /// How the ghost piece is drawn, fixed at startup
+    ghost: GhostStyle,
+    /// Draw a falling piece between rows as gravity pulls it, fixed at startup
+    smooth: bool,
+    /// Board and previews flipped left to right, for practicing the other side.
+    /// Only the picture and the keys are flipped: the game underneath plays on as
+    /// usual, so replays and the AI are unaffected.
+    mirror: bool,
+}
+
+impl View {
+    /// The action a pressed key means for the game, swapped to match a mirrored board
+    fn oriented(self, action: Action) -> Action {
+        if self.mirror {
+            action.mirrored()
+        } else {
+            action
+        }
+    }
+}
+
+/// `status` holds short messages for the side panel, such as a save confirmation
+fn draw(
+    frame: &mut Frame,
+    game: &Game,
+    bindings: &KeyBindings,
+    theme: &Theme,
+    renderer: Renderer,
+    view: View,
+    status: &[&str],
+) {
+    if frame.area().width < MIN_AREA_SIZE || frame.area().height < MIN_AREA_SIZE {
+        draw_too_small(frame, frame.area());
+        return;
+    }
+
+    let chunks = main_chunks(frame.area(), view.minimal);
+
+    draw_game_board(frame, game, theme, renderer, view, chunks[0]);
+    if view.minimal {
+        draw_compact_score(frame, game, renderer, status, chunks[0]);
+    } else {
+        draw_side_panel(frame, game, bindings, theme, status, view, chunks[1]);
+    }
+
+    if game.won {
+        draw_finished(frame, game, chunks[0]);
+    } else if game.game_over && !game.is_topping_out() {
+        draw_game_over(frame, game, chunks[0]);
+    } else if game.paused {
+        draw_paused(frame, chunks[0]);
+    } else if let Some(remaining) = game.countdown_remaining() {
+        draw_countdown(frame, remaining, chunks[0]);
+    } else if game
+        .last_perfect_clear
+        .is_some_and(|at| at.elapsed() < FEEDBACK_DURATION)
+    {
+        draw_perfect_clear(frame, chunks[0]);
+    }
+}
+
+/// Splits the screen into the board area and the side panel, which is left empty
+/// in the minimal view
+fn main_chunks(area: Rect, minimal: bool) -> [Rect; 2] {
+    let constraints = if minimal {
+        [Constraint::Percentage(100), Constraint::Length(0)]
+    } else {
+        [Constraint::Percentage(70), Constraint::Percentage(30)]
+    };
+    Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-        .split(frame.area());
+        .constraints(constraints)
+        .areas(area)
+}
+
+fn draw_game_over(frame: &mut Frame, game: &Game, area: Rect) {
+    let mut lines = vec![
+        ratatui::text::Line::styled("GAME OVER", Style::default().fg(Color::Red)),
+        ratatui::text::Line::from(""),
+        ratatui::text::Line::from(format!("Final score: {}", game.score)),
+    ];
+    lines.extend(summary_lines(game));
+    lines.push(ratatui::text::Line::from(""));
+    lines.push(ratatui::text::Line::from("R: Restart"));
+    lines.push(ratatui::text::Line::from("Other keys: Menu"));
+    let popup_area = centered_rect(area, 24, lines.len() as u16 + 2);
+    let game_over = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
 
-    draw_game_board(frame, game, chunks[0]);
-    draw_side_panel(frame, game, chunks[1]);
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(game_over, popup_area);
 }
 
-fn draw_game_board(frame: &mut Frame, game: &Game, area: Rect) {
-    // Create a temporary board with current piece
-    let mut display_board = game.board.clone();
+fn draw_finished(frame: &mut Frame, game: &Game, area: Rect) {
+    let title = match game.config.mode {
+        GameMode::Sprint | GameMode::Survival | GameMode::Zen => "Finished!",
+        GameMode::Dig => "Cleared to bottom!",
+        GameMode::Marathon => "Marathon complete!",
+    };
+    let mut lines = vec![
+        ratatui::text::Line::styled(title, Style::default().fg(Color::Green)),
+        ratatui::text::Line::from(""),
+        ratatui::text::Line::from(format!("Time: {}", format_duration(game.elapsed()))),
+        ratatui::text::Line::from(format!("Score: {}", game.score)),
+    ];
+    lines.extend(summary_lines(game));
+    lines.push(ratatui::text::Line::from(""));
+    lines.push(ratatui::text::Line::from("R: Restart"));
+    lines.push(ratatui::text::Line::from("Other keys: Menu"));
+    let popup_area = centered_rect(area, 24, lines.len() as u16 + 2);
+    let finished = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
 
-    // Add current piece to display board
-    for (row_idx, row) in game.current_piece.shape.iter().enumerate() {
-        for (col_idx, &is_filled) in row.iter().enumerate() {
-            if is_filled {
-                let board_x = game.current_piece.x + col_idx as i32;
-                let board_y = game.current_piece.y + row_idx as i32;
-                if board_y >= 0
-                    && board_y < BOARD_HEIGHT as i32
-                    && board_x >= 0
-                    && board_x < BOARD_WIDTH as i32
-                {
-                    display_board[board_y as usize][board_x as usize] =
-                        Cell::Filled(game.current_piece.color());
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(finished, popup_area);
+}
+
+/// Lines, pieces, speed and best combo, shown once a game has ended
+fn summary_lines(game: &Game) -> Vec<ratatui::text::Line<'static>> {
+    let mut lines = vec![
+        ratatui::text::Line::from(""),
+        ratatui::text::Line::from(format!("Lines: {}", game.lines)),
+        ratatui::text::Line::from(format!("Pieces: {}", game.pieces_placed)),
+        ratatui::text::Line::from(format!("PPS: {:.2}", game.pieces_per_second())),
+        ratatui::text::Line::from(format!("LPM: {:.1}", game.lines_per_minute())),
+        ratatui::text::Line::from(format!("Best combo: {}", game.max_combo)),
+        ratatui::text::Line::from(format!("Difficulty: {:?}", game.config.difficulty)),
+    ];
+    if game.config.finesse {
+        lines.push(ratatui::text::Line::from(format!(
+            "Finesse faults: {}",
+            game.finesse_faults
+        )));
+    }
+    if game.config.mode == GameMode::Survival {
+        lines.push(ratatui::text::Line::from(format!(
+            "Survived: {}",
+            format_duration(game.elapsed())
+        )));
+    }
+    lines
+}
+
+/// Formats a duration as `mm:ss.mmm`
+fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    format!(
+        "{:02}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Score, level and lines in the board's top-right corner, standing in for the side
+/// panel in the minimal view, with any status messages below
+fn draw_compact_score(
+    frame: &mut Frame,
+    game: &Game,
+    renderer: Renderer,
+    status: &[&str],
+    board_area: Rect,
+) {
+    let board = board_rect(game, renderer.fitting(game, board_area), board_area);
+    let mut lines = vec![
+        game.score.to_string(),
+        format!("Lv {}", game.level),
+        format!("Lines {}", game.lines),
+    ];
+    lines.extend(status.iter().map(|text| text.to_string()));
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0) as u16;
+    let area = Rect::new(
+        (board.x + board.width).saturating_sub(width + 1),
+        board.y + 1,
+        width,
+        lines.len() as u16,
+    )
+    .intersection(board);
+    let overlay = Paragraph::new(
+        lines
+            .into_iter()
+            .map(ratatui::text::Line::from)
+            .collect::<Vec<_>>(),
+    )
+    .alignment(Alignment::Right)
+    .style(Style::default().fg(Color::Yellow));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(overlay, area);
+}
+
+/// Frame rate, gravity speed, level and game time in the board's top-left corner
+fn draw_debug_overlay(
+    frame: &mut Frame,
+    game: &Game,
+    renderer: Renderer,
+    minimal: bool,
+    stats: &FrameStats,
+) {
+    let board_area = main_chunks(frame.area(), minimal)[0];
+    if board_area.width < MIN_AREA_SIZE || board_area.height < MIN_AREA_SIZE {
+        return;
+    }
+    let board = board_rect(game, renderer.fitting(game, board_area), board_area);
+    let lines = vec![
+        ratatui::text::Line::from(format!("FPS   {:.0}", stats.fps)),
+        ratatui::text::Line::from(format!("Tick  {}ms", game.tick_rate().as_millis())),
+        ratatui::text::Line::from(format!("Level {}", game.level)),
+        ratatui::text::Line::from(format!("Time  {}", format_duration(game.elapsed()))),
+    ];
+    let area = Rect::new(board.x + 1, board.y + 1, 17, lines.len() as u16).intersection(board);
+    let overlay = Paragraph::new(lines).style(Style::default().fg(Color::Yellow));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(overlay, area);
+}
+
+/// Latency histogram in the bottom left corner of the screen
+fn draw_latency_overlay(frame: &mut Frame, latency: &LatencyStats) {
+    let lines: Vec<ratatui::text::Line> = latency
+        .lines()
+        .into_iter()
+        .map(ratatui::text::Line::from)
+        .collect();
+    let height = lines.len() as u16;
+    let screen = frame.area();
+    let area = Rect::new(screen.x, screen.bottom().saturating_sub(height), 28, height)
+        .intersection(screen);
+    let overlay = Paragraph::new(lines).style(Style::default().fg(Color::Yellow));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(overlay, area);
+}
+
+/// The seconds left before play starts, as one big digit
+fn draw_countdown(frame: &mut Frame, remaining: Duration, area: Rect) {
+    // Rounded up, so it reads 3, 2, 1 and never 0
+    let seconds = (remaining.as_millis().div_ceil(1000) as usize).clamp(1, BIG_DIGITS.len());
+    let lines: Vec<ratatui::text::Line> = BIG_DIGITS[seconds - 1]
+        .iter()
+        .map(|row| {
+            let cells: String = row
+                .chars()
+                .flat_map(|c| [if c == '#' { '█' } else { ' ' }; 2])
+                .collect();
+            ratatui::text::Line::from(cells)
+        })
+        .collect();
+
+    let popup_area = centered_rect(area, 10, lines.len() as u16 + 2);
+    let countdown = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::Yellow))
+        .block(Block::default().borders(Borders::ALL));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(countdown, popup_area);
+}
+
+/// Banner over the freshly emptied board
+fn draw_perfect_clear(frame: &mut Frame, area: Rect) {
+    let popup_area = centered_rect(area, 20, 3);
+    let banner = Paragraph::new("PERFECT CLEAR!")
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL))
+        .style(
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(banner, popup_area);
+}
+
+fn draw_paused(frame: &mut Frame, area: Rect) {
+    let popup_area = centered_rect(area, 20, 3);
+    let paused = Paragraph::new("PAUSED")
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().fg(Color::Yellow));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(paused, popup_area);
+}
+
+/// Returns a rect of the given size centered within `area`, shrunk to fit if needed
+fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// With `grid` on, empty cells alternate with a second shade in a checkerboard, so
+/// columns and rows are easier to count
+fn draw_game_board(
+    frame: &mut Frame,
+    game: &Game,
+    theme: &Theme,
+    renderer: Renderer,
+    view: View,
+    area: Rect,
+) {
+    let renderer = renderer.fitting(game, area);
+    // Need room for the borders plus at least one cell, or the size math below underflows
+    if area.width < MIN_AREA_SIZE
+        || area.height < MIN_AREA_SIZE
+        || (area.width as usize) < renderer.cell_width() + 2
+    {
+        draw_too_small(frame, area);
+        return;
+    }
+
+    // Colors of the locked stack, with the pieces in play drawn on top
+    let mut display_colors: Vec<Vec<Color>> = game
+        .board
+        .rows()
+        .iter()
+        .enumerate()
+        .map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(x, cell)| match cell {
+                    Cell::Empty if view.grid && (x + y) % 2 == 1 => theme.grid,
+                    Cell::Empty => theme.empty,
+                    Cell::Filled(piece_type) => theme.piece(*piece_type),
+                    Cell::Garbage => theme.garbage,
+                })
+                .collect()
+        })
+        .collect();
+
+    // A fresh hard drop leaves a faint trail through the empty cells it fell past
+    if let Some((trail, at)) = game.last_hard_drop {
+        if at.elapsed() < TRAIL_DURATION {
+            for (y, row) in display_colors.iter_mut().enumerate() {
+                for (x, color) in row.iter_mut().enumerate() {
+                    if trail.contains(x as i32, y as i32) && game.board.get(x, y) == Cell::Empty {
+                        *color = theme.ghost;
+                    }
                 }
             }
         }
     }
 
-    // Scale vertically by repeating each row
-    let vertical_scale = 2; // Each cell is 2 rows high
-    let mut scaled_rows = Vec::with_capacity(BOARD_HEIGHT * vertical_scale);
+    // Add ghost piece first so the current piece overlays it. Outlines go on top
+    // of the drawn board instead, being characters rather than colors.
+    let ghost = match (view.ghost, renderer) {
+        _ if !game.config.show_ghost => GhostStyle::None,
+        (GhostStyle::Outline, Renderer::HalfBlocks) => GhostStyle::Dim,
+        (style, _) => style,
+    };
+    if ghost == GhostStyle::Dim {
+        let (ghost_x, ghost_y) = game.ghost_position();
+        overlay_shape(
+            &mut display_colors,
+            &game.current_piece.shape,
+            ghost_x,
+            ghost_y,
+            theme.ghost,
+        );
+    }
+
+    // With smooth falling, a piece partway to its next gravity step is drawn that
+    // far below its row, in slices of half a terminal row
+    let sub_rows = renderer.sub_rows();
+    let fall_offset = if view.smooth {
+        (game.fall_progress() * sub_rows as f32) as usize
+    } else {
+        0
+    };
+    let without_piece = (fall_offset > 0).then(|| display_colors.clone());
 
-    for y in 0..BOARD_HEIGHT {
-        let row_spans: Vec<ratatui::text::Span> = display_board[y]
-            .iter()
-            .map(|cell| match cell {
-                Cell::Empty => {
-                    ratatui::text::Span::styled(CELL_CHARS, Style::default().bg(Color::Gray))
-                }
-                Cell::Filled(color) => {
-                    ratatui::text::Span::styled(CELL_CHARS, Style::default().bg(*color))
-                }
-            })
-            .collect();
+    // Add current piece to display board
+    overlay_shape(
+        &mut display_colors,
+        &game.current_piece.shape,
+        game.current_piece.x,
+        game.current_piece.y,
+        theme.piece(game.current_piece.piece_type),
+    );
 
-        let line = ratatui::text::Line::from(row_spans);
-        // Add each row multiple times for vertical scaling
-        for _ in 0..vertical_scale {
-            scaled_rows.push(line.clone());
+    // Rows being cleared alternate between white and their own colors
+    let flashing_rows = match &game.clearing {
+        Some((rows, flashed)) if (flashed.as_millis() / 50) % 2 == 0 => rows.as_slice(),
+        _ => &[],
+    };
+
+    for &y in flashing_rows {
+        display_colors[y].fill(Color::White);
+    }
+
+    // The spawn buffer above the visible field stays hidden
+    let mut lines = match without_piece {
+        Some(board) => smooth_lines(game, theme, renderer, &board, fall_offset),
+        None => renderer.lines(&display_colors[BUFFER_ROWS..]),
+    };
+    // Every renderer draws each cell as a span of its own
+    if view.mirror {
+        for line in &mut lines {
+            line.spans.reverse();
         }
     }
+    let board_widget = Paragraph::new(lines).block(Block::default().title("Tetris"));
+    let rect = board_rect(game, renderer, area);
+    frame.render_widget(board_widget, rect);
+    if ghost == GhostStyle::Outline {
+        draw_ghost_outline(frame, game, theme, renderer, rect, view.mirror);
+    }
 
-    let board_widget = Paragraph::new(scaled_rows).block(Block::default().title("Tetris"));
+    // A bar in the margin under the board shrinks as a grounded piece's lock delay
+    // runs out. A board cut down to fit has no margin left to put it in.
+    let full_height = renderer.rows_for(game.config.height) + 2;
+    if let Some(progress) = game.lock_progress() {
+        if !game.paused && rect.height as usize >= full_height {
+            let board_width = (game.board.width() * renderer.cell_width()) as u16;
+            let remaining = ((1.0 - progress) * board_width as f32).ceil() as u16;
+            let bar = Paragraph::new("━".repeat(remaining as usize))
+                .style(Style::default().fg(theme.piece(game.current_piece.piece_type)));
+            let bar_area = Rect::new(rect.x, rect.bottom() - 1, remaining, 1).intersection(rect);
+            frame.render_widget(bar, bar_area);
+        }
+    }
+}
 
-    // Calculate the maximum space we can use while maintaining aspect ratio
-    let available_height = area.height as usize - 2; // -2 for borders
-    let available_width = (area.width as usize - 2) / CELL_CHARS.len(); // Account for cell width
+/// The visible rows of `board`, drawn without the current piece, with the piece over
+/// them `offset` slices of `Renderer::sub_rows` below its row. Nothing flashes while
+/// a piece is falling, so the clear animation needs no handling here.
+fn smooth_lines(
+    game: &Game,
+    theme: &Theme,
+    renderer: Renderer,
+    board: &[Vec<Color>],
+    offset: usize,
+) -> Vec<ratatui::text::Line<'static>> {
+    let sub_rows = renderer.sub_rows();
+    let mut slices: Vec<Vec<Color>> = board[BUFFER_ROWS..]
+        .iter()
+        .flat_map(|row| std::iter::repeat_n(row.clone(), sub_rows))
+        .collect();
+    let piece = &game.current_piece;
+    let color = theme.piece(piece.piece_type);
+    for (col, row) in piece.shape.cells() {
+        let x = (piece.x + col as i32) as usize;
+        let top = (piece.y + row as i32 - BUFFER_ROWS as i32) * sub_rows as i32 + offset as i32;
+        for slice in top..top + sub_rows as i32 {
+            if let Some(cells) = usize::try_from(slice).ok().and_then(|y| slices.get_mut(y)) {
+                cells[x] = color;
+            }
+        }
+    }
+    half_block_lines(&slices, renderer.cell_width())
+}
 
-    let height_ratio = available_height as f32 / (BOARD_HEIGHT * vertical_scale) as f32;
-    let width_ratio = available_width as f32 / BOARD_WIDTH as f32;
+/// Draws the ghost piece over the board in `rect` as hollow boxes, one on each of
+/// its cells along the edge of the shape, keeping the background underneath.
+/// `mirror` flips it left to right along with the board.
+fn draw_ghost_outline(
+    frame: &mut Frame,
+    game: &Game,
+    theme: &Theme,
+    renderer: Renderer,
+    rect: Rect,
+    mirror: bool,
+) {
+    // Terminal rows of one cell's box, top to bottom
+    let box_rows: Vec<String> = match renderer {
+        Renderer::Blocks { scale: 1, width } => {
+            vec!["[".to_string() + &" ".repeat(width - 2) + "]"]
+        }
+        Renderer::Blocks { scale, width } => {
+            let bar = "─".repeat(width - 2);
+            let mut rows = vec![format!("┌{bar}┐")];
+            rows.extend(std::iter::repeat_n(
+                format!("│{}│", " ".repeat(width - 2)),
+                scale - 2,
+            ));
+            rows.push(format!("└{bar}┘"));
+            rows
+        }
+        Renderer::Compact => vec!["□".to_string()],
+        Renderer::HalfBlocks => return,
+    };
 
-    // Use the smaller ratio to maintain aspect ratio
-    let ratio = height_ratio.min(width_ratio);
+    let shape = &game.current_piece.shape;
+    let (ghost_x, ghost_y) = game.ghost_position();
+    let in_shape = |col: i32, row: i32| {
+        (0..shape.size() as i32).contains(&col)
+            && (0..shape.size() as i32).contains(&row)
+            && shape.is_filled(col as usize, row as usize)
+    };
+    let piece_cells: Vec<_> = shape
+        .cells()
+        .map(|(col, row)| {
+            (
+                game.current_piece.x + col as i32,
+                game.current_piece.y + row as i32,
+            )
+        })
+        .collect();
+    let cell_width = renderer.cell_width() as i32;
+    let cell_height = box_rows.len() as i32;
 
-    let used_height = (BOARD_HEIGHT * vertical_scale) as f32 * ratio;
-    let used_width = (BOARD_WIDTH * CELL_CHARS.len()) as f32 * ratio + 2.0; // +2 for borders
+    for (col, row) in shape.cells() {
+        let (col, row) = (col as i32, row as i32);
+        let interior = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .iter()
+            .all(|&(dx, dy)| in_shape(col + dx, row + dy));
+        let (x, y) = (ghost_x + col, ghost_y + row);
+        // The piece itself covers the ghost where they overlap
+        if interior || y < BUFFER_ROWS as i32 || piece_cells.contains(&(x, y)) {
+            continue;
+        }
 
-    // Center the board in the available space
-    let vertical_padding = ((area.height as f32 - used_height) / 2.0).floor() as u16;
-    let horizontal_padding = ((area.width as f32 - used_width) / 2.0).floor() as u16;
+        // Under the title row, like the board's own lines
+        let column = if mirror {
+            game.board.width() as i32 - 1 - x
+        } else {
+            x
+        };
+        let left = rect.x as i32 + column * cell_width;
+        let top = rect.y as i32 + 1 + (y - BUFFER_ROWS as i32) * cell_height;
+        for (i, text) in box_rows.iter().enumerate() {
+            for (j, symbol) in text.chars().enumerate() {
+                let position = (left + j as i32, top + i as i32);
+                let inside = (rect.left() as i32..rect.right() as i32).contains(&position.0)
+                    && (rect.top() as i32..rect.bottom() as i32).contains(&position.1);
+                if inside {
+                    frame.buffer_mut()[(position.0 as u16, position.1 as u16)]
+                        .set_char(symbol)
+                        .set_fg(theme.ghost);
+                }
+            }
+        }
+    }
+}
 
-    let centered_area = Rect {
-        x: area.x + horizontal_padding,
-        y: area.y + vertical_padding,
-        width: used_width as u16,
-        height: used_height as u16 + 2, // +2 for borders
+/// Where the board is drawn within `area` by `renderer`
+fn board_rect(game: &Game, renderer: Renderer, area: Rect) -> Rect {
+    // Half blocks fit two board rows in each terminal row
+    let (rows, scale) = match renderer {
+        Renderer::Blocks { scale, .. } => (game.config.height, scale),
+        Renderer::Compact => (game.config.height, 1),
+        Renderer::HalfBlocks => (game.config.height.div_ceil(2), 1),
     };
+    compute_board_rect(area, game.board.width(), rows, renderer.cell_width(), scale)
+}
+
+/// The rect for a board of `board_w` by `board_h` cells, each `cell_w` columns wide
+/// and `scale` rows tall, plus a border cell on every side: centered in `area`, and
+/// cut down to it when too big. Only empty if `area` is.
+fn compute_board_rect(
+    area: Rect,
+    board_w: usize,
+    board_h: usize,
+    cell_w: usize,
+    scale: usize,
+) -> Rect {
+    let width = board_w.saturating_mul(cell_w).saturating_add(2);
+    let height = board_h.saturating_mul(scale).saturating_add(2);
+    let width = width.min(area.width as usize) as u16;
+    let height = height.min(area.height as usize) as u16;
+
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
 
-    frame.render_widget(board_widget, centered_area);
+fn draw_too_small(frame: &mut Frame, area: Rect) {
+    let message = Paragraph::new("Too small, resize me")
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::Red));
+    frame.render_widget(message, area);
 }
 
-fn draw_side_panel(frame: &mut Frame, game: &Game, area: Rect) {
-    let chunks = Layout::default()
+fn overlay_shape(colors: &mut [Vec<Color>], shape: &Shape, x: i32, y: i32, color: Color) {
+    for (col, row) in shape.cells() {
+        let board_x = x + col as i32;
+        let board_y = y + row as i32;
+        if board_y >= 0
+            && board_y < colors.len() as i32
+            && board_x >= 0
+            && board_x < colors[0].len() as i32
+        {
+            colors[board_y as usize][board_x as usize] = color;
+        }
+    }
+}
+
+/// Bordered panel with the theme's border color, if it has one
+fn panel_block<'a>(title: &'a str, theme: &Theme) -> Block<'a> {
+    let block = Block::default().borders(Borders::ALL).title(title);
+    match theme.border {
+        Some(color) => block.border_style(Style::default().fg(color)),
+        None => block,
+    }
+}
+
+fn draw_side_panel(
+    frame: &mut Frame,
+    game: &Game,
+    bindings: &KeyBindings,
+    theme: &Theme,
+    status: &[&str],
+    view: View,
+    area: Rect,
+) {
+    // The level up top in bold, blinking for a moment after it goes up
+    let mut level_style = Style::default().add_modifier(Modifier::BOLD);
+    if game.last_level_up.is_some_and(|at| {
+        let since = at.elapsed();
+        since < LEVEL_UP_FLASH && (since.as_millis() / 100) % 2 == 0
+    }) {
+        level_style = level_style.fg(Color::Black).bg(Color::Yellow);
+    }
+    let mut score_lines = vec![
+        ratatui::text::Line::styled(
+            format!(
+                "LEVEL {}  next in {}",
+                game.level,
+                game.lines_to_next_level()
+            ),
+            level_style,
+        ),
+        ratatui::text::Line::from(format!("Score: {}  High: {}", game.score, game.high_score)),
+        ratatui::text::Line::from(format!("Drop points: {}", game.drop_points)),
+        ratatui::text::Line::from(match game.config.line_goal() {
+            Some(goal) => format!("Lines: {}/{}", game.lines, goal),
+            None => format!("Lines: {}", game.lines),
+        }),
+        ratatui::text::Line::from(format!("Time: {}", format_duration(game.elapsed()))),
+    ];
+    if let Some(date) = game.config.daily {
+        score_lines.insert(
+            0,
+            ratatui::text::Line::styled(
+                format!("Daily Challenge {date}"),
+                Style::default().fg(Color::Yellow),
+            ),
+        );
+    }
+    if game.config.mode == GameMode::Dig {
+        score_lines.push(ratatui::text::Line::from(format!(
+            "Garbage left: {}",
+            game.garbage_left()
+        )));
+    }
+    if let Some(left) = game.time_to_next_rise() {
+        score_lines.push(ratatui::text::Line::from(format!(
+            "Rise {}: {}",
+            game.rise_level,
+            rise_gauge(left, game.rise_interval())
+        )));
+    }
+    if game.config.finesse {
+        score_lines.push(ratatui::text::Line::from(format!(
+            "Finesse faults: {}",
+            game.finesse_faults
+        )));
+    }
+    if game.back_to_back {
+        score_lines.push(ratatui::text::Line::from("Back-to-back x1.5"));
+    }
+    if game.combo > 0 {
+        score_lines.push(ratatui::text::Line::from(format!("Combo: {}", game.combo)));
+    }
+    score_lines.extend(status.iter().map(|&text| ratatui::text::Line::from(text)));
+    if let Some((kind, at)) = game.last_t_spin {
+        if at.elapsed() < FEEDBACK_DURATION {
+            score_lines.push(ratatui::text::Line::from(match kind {
+                TSpin::Full => "T-SPIN!",
+                TSpin::Mini => "T-SPIN MINI",
+            }));
+        }
+    }
+
+    // Upcoming pieces stacked top to bottom, separated by a blank line. A short
+    // panel shows fewer of them, down to one, so the hold box still fits below.
+    let score_height = score_lines.len() as u16 + 2;
+    let tallest = game
+        .config
+        .piece_set
+        .pieces()
+        .iter()
+        .map(|&piece_type| piece_preview_lines(piece_type, Color::Reset, false).len())
+        .max()
+        .unwrap_or(0);
+    // Room for one more piece and a blank line above it per extra hold slot
+    let extra_slots = game.config.hold_capacity.max(1) as u16 - 1;
+    let hold_height = HOLD_HEIGHT.max(tallest as u16 + 4) + extra_slots * (tallest as u16 + 1);
+    let room = area.height.saturating_sub(score_height + hold_height + 1) as usize;
+    let mut next_lines: Vec<ratatui::text::Line> = Vec::new();
+    for &piece_type in game.next_queue.iter().take(game.config.preview_count) {
+        let mut lines = piece_preview_lines(piece_type, theme.piece(piece_type), view.mirror);
+        lines.push(ratatui::text::Line::from(""));
+        if !next_lines.is_empty() && next_lines.len() + lines.len() > room {
+            break;
+        }
+        next_lines.extend(lines);
+    }
+    let next_height = next_lines.len() as u16 + 1;
+
+    // Score, next and hold keep their full size, the rest share whatever is left
+    let [top, bottom] = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3), // Score
-            Constraint::Length(6), // Next piece
-            Constraint::Min(0),    // Controls
+            Constraint::Length(score_height + next_height + hold_height),
+            Constraint::Min(0),
         ])
-        .split(area);
+        .areas(area);
+    let top_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(score_height), // Score
+            Constraint::Length(next_height),  // Next pieces
+            Constraint::Length(hold_height),  // Held piece
+        ])
+        .split(top);
+    // Without the help, the score log takes the space it leaves
+    let stats_height = game.config.piece_set.pieces().len() as u16 + 2;
+    let metrics_height = if view.metrics { METRICS_HEIGHT } else { 0 };
+    let bottom_chunks = if view.help {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(stats_height),               // Statistics
+                Constraint::Length(metrics_height),             // Stack
+                Constraint::Length(SCORE_LOG_SHOWN as u16 + 2), // Scoring
+                Constraint::Min(0),                             // Controls
+            ])
+            .split(bottom)
+    } else {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(stats_height),   // Statistics
+                Constraint::Length(metrics_height), // Stack
+                Constraint::Min(0),                 // Scoring
+            ])
+            .split(bottom)
+    };
 
     // Score
-    let score_text = format!("Score: {}", game.score);
-    let score = Paragraph::new(score_text)
-        .block(Block::default().borders(Borders::ALL).title("Score"))
+    let score = Paragraph::new(score_lines)
+        .block(panel_block("Score", theme))
         .style(Style::default().fg(Color::Yellow));
-    frame.render_widget(score, chunks[0]);
+    frame.render_widget(score, top_chunks[0]);
+
+    // Next pieces
+    let next_widget = Paragraph::new(next_lines).block(panel_block("Next", theme));
+    frame.render_widget(next_widget, top_chunks[1]);
+
+    // Held pieces, the next to come back on top, faded like the ghost until they
+    // can be swapped in again
+    let mut held_lines = Vec::new();
+    for &piece_type in &game.held {
+        if !held_lines.is_empty() {
+            held_lines.push(ratatui::text::Line::from(""));
+        }
+        let color = if game.can_hold {
+            theme.piece(piece_type)
+        } else {
+            theme.ghost
+        };
+        held_lines.extend(piece_preview_lines(piece_type, color, view.mirror));
+    }
+    // Lit up briefly after a swap, so the change doesn't go unnoticed
+    let mut held_block = panel_block("Hold", theme);
+    if game
+        .last_hold
+        .is_some_and(|at| at.elapsed() < HOLD_HIGHLIGHT)
+    {
+        held_block = held_block.border_style(
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
+    }
+    let held_widget = Paragraph::new(held_lines).block(held_block);
+    frame.render_widget(held_widget, top_chunks[2]);
+
+    // Piece statistics
+    let stats_lines: Vec<ratatui::text::Line> = game
+        .config
+        .piece_set
+        .pieces()
+        .iter()
+        .map(|piece_type| {
+            ratatui::text::Line::from(vec![
+                ratatui::text::Span::styled("  ", Style::default().bg(theme.piece(*piece_type))),
+                ratatui::text::Span::raw(format!(
+                    " {}: {}",
+                    piece_type.name(),
+                    game.piece_counts[piece_type.index()]
+                )),
+            ])
+        })
+        .collect();
+    let stats_widget = Paragraph::new(stats_lines).block(panel_block("Statistics", theme));
+    frame.render_widget(stats_widget, bottom_chunks[0]);
+
+    if view.metrics {
+        let metrics_widget =
+            Paragraph::new(metrics_lines(&game.board.metrics())).block(panel_block("Stack", theme));
+        frame.render_widget(metrics_widget, bottom_chunks[1]);
+    }
+
+    // Where the latest points came from, newest first
+    let log_lines: Vec<ratatui::text::Line> = game
+        .score_log
+        .iter()
+        .rev()
+        .take(bottom_chunks[2].height.saturating_sub(2) as usize)
+        .map(|entry| {
+            ratatui::text::Line::from(format!(
+                "#{} {} +{}",
+                entry.piece,
+                score_reason_label(entry.reason),
+                entry.points
+            ))
+        })
+        .collect();
+    let log_widget = Paragraph::new(log_lines).block(panel_block("Scoring", theme));
+    frame.render_widget(log_widget, bottom_chunks[2]);
+
+    if !view.help {
+        return;
+    }
 
     // Controls help
-    let controls = vec![
-        "Controls:",
-        "←/→: Move",
-        "↑: Rotate",
-        "↓: Soft Drop",
-        "Q: Quit",
-    ]
-    .join("\n");
+    let key = |action| bindings.key_name(action);
+    let mut controls = vec![
+        "Controls:".to_string(),
+        format!("{}/{}: Move", key(Action::MoveLeft), key(Action::MoveRight)),
+        format!("{}: Rotate", key(Action::RotateCW)),
+        format!("{}: Rotate CCW", key(Action::RotateCCW)),
+        format!("{}: Rotate 180", key(Action::Rotate180)),
+        format!("{}: Soft Drop", key(Action::SoftDrop)),
+        format!("{}: Hard Drop", key(Action::HardDrop)),
+        format!("{}: Hold", key(Action::Hold)),
+        format!("{}: Pause", key(Action::Pause)),
+        format!("{}: Save", key(Action::Save)),
+        format!("{}: Restart", key(Action::Restart)),
+        format!("{}: Quit", key(Action::Quit)),
+        format!("{}: Hide help", key(Action::ToggleHelp)),
+    ];
+    if game.config.mode == GameMode::Zen {
+        controls.insert(8, format!("{}: Undo", key(Action::Undo)));
+    }
+    let controls = controls.join("\n");
 
     let controls_widget = Paragraph::new(controls)
-        .block(Block::default().borders(Borders::ALL).title("Help"))
+        .block(panel_block("Help", theme))
         .style(Style::default().fg(Color::Gray));
-    frame.render_widget(controls_widget, chunks[2]);
+    frame.render_widget(controls_widget, bottom_chunks[3]);
+}
+
+/// Each column's height and holes lined up under one another, then the totals the
+/// AI weighs
+fn metrics_lines(metrics: &BoardMetrics) -> Vec<ratatui::text::Line<'static>> {
+    let width = metrics
+        .heights
+        .iter()
+        .chain(&metrics.holes)
+        .map(|value| value.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let row = |label: &str, values: &[usize]| {
+        let cells: Vec<String> = values
+            .iter()
+            .map(|value| format!("{value:>width$}"))
+            .collect();
+        ratatui::text::Line::from(format!("{label:<3}{}", cells.join(" ")))
+    };
+    vec![
+        row("Ht", &metrics.heights),
+        row("Hl", &metrics.holes),
+        ratatui::text::Line::from(format!(
+            "Height {}  Bumps {}",
+            metrics.aggregate_height(),
+            metrics.bumpiness()
+        )),
+        ratatui::text::Line::from(format!("Holes {}", metrics.total_holes())),
+    ]
+}
+
+/// A bar of `RISE_GAUGE_WIDTH` characters whose filled part is the share of
+/// `interval` still `left` before garbage rises
+fn rise_gauge(left: Duration, interval: Duration) -> String {
+    let filled =
+        (left.as_secs_f64() / interval.as_secs_f64() * RISE_GAUGE_WIDTH as f64).ceil() as usize;
+    let filled = filled.min(RISE_GAUGE_WIDTH);
+    "█".repeat(filled) + &"░".repeat(RISE_GAUGE_WIDTH - filled)
+}
+
+/// Short name for what a score log entry was awarded for, like "B2B Tetris"
+fn score_reason_label(reason: ScoreReason) -> String {
+    match reason {
+        ScoreReason::SoftDrop => "Soft drop".to_string(),
+        ScoreReason::HardDrop => "Hard drop".to_string(),
+        ScoreReason::Combo(step) => format!("Combo x{step}"),
+        ScoreReason::PerfectClear => "Perfect clear".to_string(),
+        ScoreReason::Survived => "Survived rise".to_string(),
+        ScoreReason::Clear {
+            lines,
+            t_spin,
+            back_to_back,
+        } => {
+            let clear = match lines {
+                0 => String::new(),
+                1 => "Single".to_string(),
+                2 => "Double".to_string(),
+                3 => "Triple".to_string(),
+                4 => "Tetris".to_string(),
+                _ => format!("{lines} lines"),
+            };
+            let label = match t_spin {
+                Some(TSpin::Full) => format!("T-spin {clear}"),
+                Some(TSpin::Mini) => format!("T-spin mini {clear}"),
+                None => clear,
+            };
+            let label = label.trim_end();
+            if back_to_back {
+                format!("B2B {label}")
+            } else {
+                label.to_string()
+            }
+        }
+    }
+}
+
+/// Preview of a piece in spawn orientation, leaving out its empty rows, flipped
+/// left to right if `mirror` is set
+fn piece_preview_lines(
+    piece_type: TetrominoType,
+    color: Color,
+    mirror: bool,
+) -> Vec<ratatui::text::Line<'static>> {
+    Tetromino::spawn_shape(piece_type)
+        .to_rows()
+        .into_iter()
+        .filter(|row| row.contains(&true))
+        .map(|mut row| {
+            if mirror {
+                row.reverse();
+            }
+            let row_spans: Vec<ratatui::text::Span> = row
+                .iter()
+                .map(|&is_filled| {
+                    if is_filled {
+                        ratatui::text::Span::styled(CELL_CHARS, Style::default().bg(color))
+                    } else {
+                        ratatui::text::Span::raw(CELL_CHARS)
+                    }
+                })
+                .collect();
+            ratatui::text::Line::from(row_spans)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+
+    /// Draws just the side panel, 30 columns wide, and hands back what it drew.
+    fn render_side_panel(game: &Game, view: View, height: u16) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(30, height)).unwrap();
+        terminal
+            .draw(|f| {
+                draw_side_panel(
+                    f,
+                    game,
+                    &KeyBindings::default(),
+                    &Theme::default(),
+                    &[],
+                    view,
+                    f.area(),
+                )
+            })
+            .unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    /// Every symbol on screen, row after row, for checking what text shows up.
+    fn screen_text(buffer: &Buffer) -> String {
+        buffer.content().iter().map(|c| c.symbol()).collect()
+    }
+
+    #[test]
+    fn next_preview_shows_the_piece_as_it_spawns() {
+        let drawn = |rows: Vec<Vec<bool>>| -> Vec<String> {
+            rows.into_iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&filled| if filled { '#' } else { '.' })
+                        .collect()
+                })
+                .collect()
+        };
+        let mut game = Game::with_config(GameConfig {
+            seed: Some(3),
+            piece_set: PieceSet::Pentominoes,
+            ..GameConfig::default()
+        });
+
+        for _ in 0..12 {
+            let next = game.next_queue[0];
+            let preview = piece_preview_lines(next, Color::Red, false)
+                .iter()
+                .map(|line| {
+                    line.spans
+                        .iter()
+                        .map(|span| span.style.bg == Some(Color::Red))
+                        .collect()
+                })
+                .collect();
+            game.hard_drop();
+            game.board = tetris_rs::board::Board::new(game.board.width(), game.board.height());
+
+            assert_eq!(game.current_piece.piece_type, next);
+            let spawned = game
+                .current_piece
+                .shape
+                .to_rows()
+                .into_iter()
+                .filter(|row| row.contains(&true))
+                .collect();
+            assert_eq!(drawn(preview), drawn(spawned), "{next:?}");
+        }
+    }
+
+    fn render_at(width: u16, height: u16) {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        let game = Game::with_seed(1);
+        for renderer in [Renderer::default(), Renderer::HalfBlocks] {
+            terminal
+                .draw(|f| {
+                    draw(
+                        f,
+                        &game,
+                        &KeyBindings::default(),
+                        &Theme::default(),
+                        renderer,
+                        View::default(),
+                        &[],
+                    )
+                })
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn half_blocks_pack_two_rows_into_each_line() {
+        let rows = vec![
+            vec![Color::Red, Color::Gray],
+            vec![Color::Blue, Color::Red],
+            vec![Color::Green, Color::Gray],
+        ];
+
+        let lines = Renderer::HalfBlocks.lines(&rows);
+
+        assert_eq!(lines.len(), 2);
+        let top_left = &lines[0].spans[0];
+        assert_eq!(top_left.content, "▀");
+        assert_eq!(top_left.style.fg, Some(Color::Red));
+        assert_eq!(top_left.style.bg, Some(Color::Blue));
+        // The odd row out leaves the lower half blank
+        assert_eq!(lines[1].spans[1].style.bg, Some(Color::Reset));
+
+        // Cells in the terminal's own background only ever go behind the glyph
+        let lines = Renderer::HalfBlocks.lines(&[
+            vec![Color::Reset, Color::Reset, Color::Red],
+            vec![Color::Blue, Color::Reset, Color::Reset],
+        ]);
+        let styles: Vec<_> = lines[0]
+            .spans
+            .iter()
+            .map(|span| (span.content.as_ref(), span.style.fg, span.style.bg))
+            .collect();
+        assert_eq!(
+            styles,
+            [
+                ("▄", Some(Color::Blue), Some(Color::Reset)),
+                (" ", None, Some(Color::Reset)),
+                ("▀", Some(Color::Red), Some(Color::Reset)),
+            ]
+        );
+        assert_eq!(
+            Renderer::default().lines(&rows).len(),
+            3 * DEFAULT_VERTICAL_SCALE
+        );
+        let tall = Renderer::default().with_scale(3);
+        assert_eq!(tall.lines(&rows).len(), 9);
+        assert_eq!(tall.rows_for(3), 9);
+    }
+
+    #[test]
+    fn board_rect_is_centered_and_stays_inside_the_area() {
+        // Very wide: full height centered horizontally
+        let wide = Rect::new(5, 3, 200, 42);
+        assert_eq!(
+            compute_board_rect(wide, 10, 20, 4, 2),
+            Rect::new(84, 3, 42, 42)
+        );
+        // Very tall: full width centered vertically
+        let tall = Rect::new(0, 0, 42, 300);
+        assert_eq!(
+            compute_board_rect(tall, 10, 20, 4, 2),
+            Rect::new(0, 129, 42, 42)
+        );
+        // Exactly fitting, and cut down to the area when too big
+        let exact = Rect::new(2, 2, 42, 22);
+        assert_eq!(compute_board_rect(exact, 10, 20, 4, 1), exact);
+        assert_eq!(compute_board_rect(exact, 10, 20, 4, 3), exact);
+
+        for (width, height) in [(1, 1), (1, 300), (300, 1), (3, 7), (43, 41)] {
+            let area = Rect::new(7, 9, width, height);
+            let board = compute_board_rect(area, 10, 20, 4, 2);
+            assert!(!board.is_empty(), "{width}x{height}");
+            assert_eq!(board.intersection(area), board, "{width}x{height}");
+        }
+        assert!(compute_board_rect(Rect::new(0, 0, 0, 10), 10, 20, 4, 2).is_empty());
+    }
+
+    #[test]
+    fn every_cell_width_keeps_the_board_centered() {
+        let theme = Theme::default();
+        let mut game = Game::with_seed(1);
+        game.config.show_ghost = false;
+        game.current_piece = Tetromino::new(TetrominoType::O, game.board.width());
+        game.current_piece.y = BUFFER_ROWS as i32;
+        while game.move_piece(1, 0) {}
+        let area = Rect::new(0, 0, 61, 44);
+
+        for width in MIN_CELL_WIDTH..=DEFAULT_CELL_WIDTH {
+            let renderer = Renderer::default().with_cell_width(width);
+            let rect = board_rect(&game, renderer, area);
+            assert_eq!(rect.width as usize, 10 * width + 2);
+            let (left, right) = (rect.x - area.x, area.right() - rect.right());
+            assert!(left.abs_diff(right) <= 1, "{width}: {left} and {right}");
+
+            // The piece against the right wall ends on the board's last column
+            let mut terminal = Terminal::new(TestBackend::new(area.width, area.height)).unwrap();
+            terminal
+                .draw(|f| draw_game_board(f, &game, &theme, renderer, View::default(), area))
+                .unwrap();
+            let color = theme.piece(TetrominoType::O);
+            let columns: Vec<u16> = (0..area.width)
+                .filter(|&x| terminal.backend().buffer()[(x, rect.y + 1)].bg == color)
+                .collect();
+            let last_column = rect.x + 10 * width as u16 - 1;
+            assert_eq!(columns.first(), Some(&(last_column + 1 - 2 * width as u16)));
+            assert_eq!(columns.last(), Some(&last_column));
+        }
+        // Out of range widths are brought back in
+        assert_eq!(Renderer::default().with_cell_width(9), Renderer::default());
+        assert_eq!(
+            Renderer::default().with_cell_width(0).cell_width(),
+            MIN_CELL_WIDTH
+        );
+    }
+
+    #[test]
+    fn small_areas_switch_to_a_smaller_renderer() {
+        let game = Game::with_seed(1);
+        let blocks = Rect::new(0, 0, 42, 42);
+        let compact = Rect::new(0, 0, 12, 22);
+        let tiny = Rect::new(0, 0, 12, 12);
+
+        let default = Renderer::default();
+        assert_eq!(default.fitting(&game, blocks), default);
+        assert_eq!(default.fitting(&game, compact), Renderer::Compact);
+        assert_eq!(default.fitting(&game, tiny), Renderer::HalfBlocks);
+        // Taller blocks need more room
+        let tall = default.with_scale(3);
+        assert_eq!(tall.fitting(&game, blocks), Renderer::Compact);
+        assert_eq!(tall.fitting(&game, Rect::new(0, 0, 42, 62)), tall);
+        // Never grows past the configured renderer
+        assert_eq!(
+            Renderer::HalfBlocks.fitting(&game, blocks),
+            Renderer::HalfBlocks
+        );
+        assert_eq!(
+            Renderer::Compact.lines(&[vec![Color::Red; 10]])[0].width(),
+            10
+        );
+    }
+
+    #[test]
+    fn tiny_terminals_do_not_panic() {
+        for width in 0..12 {
+            for height in 0..12 {
+                render_at(width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn narrow_board_area_does_not_panic() {
+        // The board gets 70% of the width, so this leaves it just a few columns
+        render_at(8, 40);
+        render_at(80, 3);
+    }
+
+    #[test]
+    fn game_over_screen_summarizes_the_run() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 40)).unwrap();
+        let mut game = Game::with_seed(1);
+        game.lines = 12;
+        game.pieces_placed = 34;
+        game.max_combo = 3;
+        terminal
+            .draw(|f| draw_game_over(f, &game, f.area()))
+            .unwrap();
+
+        let screen = screen_text(terminal.backend().buffer());
+        assert!(screen.contains("Lines: 12"));
+        assert!(screen.contains("Pieces: 34"));
+        assert!(screen.contains("PPS:"));
+        assert!(screen.contains("LPM:"));
+        assert!(screen.contains("Best combo: 3"));
+        assert!(screen.contains("Difficulty: Normal"));
+    }
+
+    #[test]
+    fn score_log_labels_name_the_clear() {
+        let clear = |lines, t_spin, back_to_back| {
+            score_reason_label(ScoreReason::Clear {
+                lines,
+                t_spin,
+                back_to_back,
+            })
+        };
+        assert_eq!(clear(4, None, true), "B2B Tetris");
+        assert_eq!(clear(2, Some(TSpin::Full), false), "T-spin Double");
+        assert_eq!(clear(0, Some(TSpin::Mini), false), "T-spin mini");
+        assert_eq!(score_reason_label(ScoreReason::Combo(3)), "Combo x3");
+    }
+
+    #[test]
+    fn rise_gauge_empties_as_the_rise_nears() {
+        let interval = Duration::from_secs(8);
+        assert_eq!(rise_gauge(interval, interval), "████████");
+        assert_eq!(rise_gauge(Duration::from_secs(3), interval), "███░░░░░");
+        assert_eq!(rise_gauge(Duration::ZERO, interval), "░░░░░░░░");
+    }
+
+    #[test]
+    fn debug_overlay_shows_frame_rate() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 40)).unwrap();
+        let game = Game::with_seed(1);
+        let stats = FrameStats {
+            fps: 59.6,
+            last_frame: None,
+        };
+        terminal
+            .draw(|f| draw_debug_overlay(f, &game, Renderer::default(), false, &stats))
+            .unwrap();
+
+        let screen = screen_text(terminal.backend().buffer());
+        assert!(screen.contains("FPS   60"));
+        assert!(screen.contains("Level 0"));
+
+        // Too small to hold the board, so nothing is drawn rather than panicking
+        for size in 0..MIN_AREA_SIZE * 2 {
+            let mut terminal = Terminal::new(TestBackend::new(size, size)).unwrap();
+            terminal
+                .draw(|f| draw_debug_overlay(f, &game, Renderer::default(), false, &stats))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn minimal_view_drops_the_side_panel_for_a_score_overlay() {
+        let mut game = Game::with_seed(1);
+        game.score = 4321;
+        let view = View {
+            minimal: true,
+            ..View::default()
+        };
+        let mut terminal = Terminal::new(TestBackend::new(30, 40)).unwrap();
+        terminal
+            .draw(|f| {
+                draw(
+                    f,
+                    &game,
+                    &KeyBindings::default(),
+                    &Theme::default(),
+                    Renderer::default(),
+                    view,
+                    &["Saved"],
+                )
+            })
+            .unwrap();
+
+        let screen = screen_text(terminal.backend().buffer());
+        assert!(!screen.contains("Next"));
+        assert!(screen.contains("4321"));
+        assert!(screen.contains("Lv 0"));
+        assert!(screen.contains("Saved"));
+        assert_eq!(main_chunks(Rect::new(0, 0, 30, 40), true)[0].width, 30);
+    }
+
+    #[test]
+    fn hold_box_shows_every_held_piece() {
+        let theme = Theme::default();
+        let mut game = Game::with_seed(1);
+        game.config.hold_capacity = 3;
+        game.held = [TetrominoType::T, TetrominoType::O, TetrominoType::I].into();
+        // Faded, so they stand apart from the colors of the rest of the panel
+        game.can_hold = false;
+
+        let faded = render_side_panel(&game, View::default(), 60)
+            .content()
+            .iter()
+            .filter(|cell| cell.bg == theme.ghost)
+            .count();
+        assert_eq!(faded, 3 * 4 * CELL_CHARS.len());
+    }
+
+    #[test]
+    fn short_panels_keep_the_next_and_hold_boxes() {
+        let mut game = Game::with_seed(1);
+        game.held.push_back(TetrominoType::T);
+        for height in [20, 26, 34, 60] {
+            let buffer = render_side_panel(
+                &game,
+                View {
+                    help: true,
+                    ..View::default()
+                },
+                height,
+            );
+
+            let rows: Vec<String> = (0..height)
+                .map(|y| (0..30).map(|x| buffer[(x, y)].symbol()).collect())
+                .collect();
+            let hold = rows
+                .iter()
+                .position(|row| row.contains("Hold"))
+                .unwrap_or_else(|| panic!("no hold box at height {height}"));
+            assert!(
+                rows[hold + 5].starts_with('└'),
+                "hold box cut short at {height}"
+            );
+            let next = rows.iter().position(|row| row.contains("Next")).unwrap();
+            // At least one upcoming piece, its colored cells between the borders
+            let colored = (next as u16 + 1..hold as u16)
+                .any(|y| (1..29).any(|x| buffer[(x, y)].bg != Color::Reset));
+            assert!(colored, "no upcoming piece at height {height}");
+        }
+    }
+
+    #[test]
+    fn level_blinks_after_going_up() {
+        let mut game = Game::with_seed(1);
+        game.lines = 17;
+        game.level = 1;
+        let level_cell = |game: &Game| {
+            let buffer = render_side_panel(
+                game,
+                View {
+                    help: true,
+                    ..View::default()
+                },
+                60,
+            );
+            let text: String = (1..29).map(|x| buffer[(x, 1)].symbol()).collect();
+            (text, buffer[(1, 1)].bg)
+        };
+
+        let (text, background) = level_cell(&game);
+        assert!(text.starts_with("LEVEL 1  next in 3"));
+        assert_eq!(background, Color::Reset);
+
+        game.last_level_up = Some(Instant::now());
+        assert_eq!(level_cell(&game).1, Color::Yellow);
+        game.last_level_up = Some(Instant::now() - LEVEL_UP_FLASH);
+        assert_eq!(level_cell(&game).1, Color::Reset);
+    }
+
+    #[test]
+    fn hold_box_lights_up_right_after_a_swap() {
+        let mut game = Game::with_seed(1);
+        let hold_corner_style = |game: &Game| {
+            let buffer = render_side_panel(
+                game,
+                View {
+                    help: true,
+                    ..View::default()
+                },
+                60,
+            );
+            let hold = (0..60)
+                .find(|&y| (1..5).map(|x| buffer[(x, y)].symbol()).collect::<String>() == "Hold")
+                .unwrap();
+            buffer[(0, hold)].style()
+        };
+        assert!(!hold_corner_style(&game)
+            .add_modifier
+            .contains(Modifier::BOLD));
+
+        game.hold();
+        assert!(hold_corner_style(&game)
+            .add_modifier
+            .contains(Modifier::BOLD));
+
+        game.last_hold = Some(Instant::now() - HOLD_HIGHLIGHT);
+        assert!(!hold_corner_style(&game)
+            .add_modifier
+            .contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn hidden_help_gives_its_room_to_the_score_log() {
+        let mut game = Game::with_seed(1);
+        for piece in 1..=10 {
+            game.score_log.push_back(tetris_rs::game::ScoreEntry {
+                piece,
+                reason: ScoreReason::HardDrop,
+                points: 2,
+            });
+        }
+        let screen = |help| {
+            screen_text(&render_side_panel(
+                &game,
+                View {
+                    help,
+                    ..View::default()
+                },
+                60,
+            ))
+        };
+
+        let shown = screen(true);
+        assert!(shown.contains("Controls:"));
+        assert!(!shown.contains("#6 "));
+        let hidden = screen(false);
+        assert!(!hidden.contains("Controls:"));
+        assert!(hidden.contains("#1 Hard drop"));
+    }
+
+    #[test]
+    fn metrics_line_up_under_their_columns() {
+        let metrics = BoardMetrics {
+            heights: vec![12, 0, 3],
+            holes: vec![1, 0, 0],
+        };
+
+        let lines: Vec<String> = metrics_lines(&metrics)
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+
+        assert_eq!(lines[0], "Ht 12  0  3");
+        assert_eq!(lines[1], "Hl  1  0  0");
+        assert_eq!(lines[2], "Height 15  Bumps 15");
+        assert_eq!(lines[3], "Holes 1");
+    }
+
+    #[test]
+    fn lock_delay_bar_shrinks_under_a_grounded_piece() {
+        let theme = Theme::default();
+        let mut game = Game::with_seed(1);
+        let bar = |game: &Game| {
+            let mut terminal = Terminal::new(TestBackend::new(12, 22)).unwrap();
+            terminal
+                .draw(|f| {
+                    draw_game_board(
+                        f,
+                        game,
+                        &theme,
+                        Renderer::Compact,
+                        View::default(),
+                        f.area(),
+                    )
+                })
+                .unwrap();
+            // The margin under the bottom row of the board
+            (0..12)
+                .filter(|&x| terminal.backend().buffer()[(x, 21)].symbol() == "━")
+                .count()
+        };
+        assert_eq!(bar(&game), 0);
+
+        let (_, landing_y) = game.ghost_position();
+        game.current_piece.y = landing_y;
+        game.lock_timer = Some(Duration::ZERO);
+        assert_eq!(bar(&game), 10);
+        game.lock_timer = Some(game.config.lock_delay / 2);
+        assert!((4..=5).contains(&bar(&game)));
+        game.paused = true;
+        assert_eq!(bar(&game), 0);
+    }
+
+    #[test]
+    fn ghost_outline_draws_a_box_on_each_ghost_cell() {
+        let theme = Theme::default();
+        let game = Game::with_seed(1);
+        let count = |renderer: Renderer, ghost: GhostStyle, symbol: &str| {
+            let mut terminal = Terminal::new(TestBackend::new(44, 44)).unwrap();
+            terminal
+                .draw(|f| {
+                    let view = View {
+                        ghost,
+                        ..View::default()
+                    };
+                    draw_game_board(f, &game, &theme, renderer, view, f.area())
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            buffer
+                .content()
+                .iter()
+                .filter(|cell| cell.symbol() == symbol && cell.fg == theme.ghost)
+                .count()
+        };
+
+        // No tetromino cell is surrounded on all four sides, so every one gets a box
+        assert_eq!(count(Renderer::Compact, GhostStyle::Outline, "□"), 4);
+        assert_eq!(count(Renderer::Compact, GhostStyle::Dim, "□"), 0);
+        let blocks = Renderer::default();
+        assert_eq!(count(blocks, GhostStyle::Outline, "┌"), 4);
+        assert_eq!(count(blocks, GhostStyle::Outline, "└"), 4);
+        assert_eq!(count(blocks, GhostStyle::None, "┌"), 0);
+    }
+
+    #[test]
+    fn smooth_falling_draws_the_piece_half_a_row_lower() {
+        let theme = Theme::default();
+        let mut game = Game::with_seed(1);
+        game.state = tetris_rs::game::GameState::Playing;
+        game.current_piece.y += 5;
+        game.gravity_accumulator = game.tick_rate() / 2;
+        let color = theme.piece(game.current_piece.piece_type);
+        // Every half-row slice of a terminal cell the piece covers, as (column, slice)
+        let piece_halves = |game: &Game, smooth: bool| {
+            let mut terminal = Terminal::new(TestBackend::new(12, 22)).unwrap();
+            terminal
+                .draw(|f| {
+                    let view = View {
+                        smooth,
+                        ..View::default()
+                    };
+                    draw_game_board(f, game, &theme, Renderer::Compact, view, f.area())
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            let mut halves = Vec::new();
+            for y in 0..22 {
+                for x in 0..12 {
+                    let cell = &buffer[(x, y)];
+                    let (top, bottom) = match cell.symbol() {
+                        "▀" => (cell.fg, cell.bg),
+                        _ => (cell.bg, cell.bg),
+                    };
+                    halves.extend([(x, 2 * y, top), (x, 2 * y + 1, bottom)]);
+                }
+            }
+            halves
+                .into_iter()
+                .filter(|&(_, _, half)| half == color)
+                .map(|(x, slice, _)| (x, slice))
+                .collect::<Vec<_>>()
+        };
+
+        let plain = piece_halves(&game, false);
+        assert_eq!(plain.len(), 8);
+        let lowered: Vec<_> = plain.iter().map(|&(x, slice)| (x, slice + 1)).collect();
+        let mut smooth = piece_halves(&game, true);
+        smooth.sort_by_key(|&(x, slice)| (slice, x));
+        let mut expected = lowered;
+        expected.sort_by_key(|&(x, slice)| (slice, x));
+        assert_eq!(smooth, expected);
+
+        // Nothing to draw in between once the piece can't fall
+        game.paused = true;
+        assert_eq!(piece_halves(&game, true), plain);
+    }
+
+    #[test]
+    fn mirrored_view_flips_the_board_and_the_keys() {
+        let theme = Theme::default();
+        let mut game = Game::with_seed(1);
+        game.config.show_ghost = false;
+        game.current_piece = Tetromino::new(TetrominoType::O, game.board.width());
+        game.current_piece.y = BUFFER_ROWS as i32;
+        while game.move_piece(-1, 0) {}
+        let color = theme.piece(TetrominoType::O);
+        let piece_columns = |mirror: bool| {
+            let mut terminal = Terminal::new(TestBackend::new(12, 22)).unwrap();
+            terminal
+                .draw(|f| {
+                    let view = View {
+                        mirror,
+                        ..View::default()
+                    };
+                    draw_game_board(f, &game, &theme, Renderer::Compact, view, f.area())
+                })
+                .unwrap();
+            // The top visible row of the board, under the title
+            (0..10)
+                .filter(|&x| terminal.backend().buffer()[(x, 1)].bg == color)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(piece_columns(false), [0, 1]);
+        assert_eq!(piece_columns(true), [8, 9]);
+
+        // Moving towards the wall on screen moves away from the game's own left wall
+        let view = View {
+            mirror: true,
+            ..View::default()
+        };
+        assert_eq!(view.oriented(Action::MoveLeft), Action::MoveRight);
+        assert_eq!(view.oriented(Action::RotateCW), Action::RotateCCW);
+        assert_eq!(View::default().oriented(Action::MoveLeft), Action::MoveLeft);
+    }
+
+    #[test]
+    fn grid_checkers_only_the_empty_cells() {
+        let theme = Theme::default();
+        let mut game = Game::with_seed(1);
+        let bottom = game.board.height() - 1;
+        game.board.set(1, bottom, TetrominoType::S);
+        game.config.show_ghost = false;
+        let backgrounds = |grid: bool| {
+            let mut terminal = Terminal::new(TestBackend::new(12, 22)).unwrap();
+            terminal
+                .draw(|f| {
+                    let view = View {
+                        grid,
+                        ..View::default()
+                    };
+                    draw_game_board(f, &game, &theme, Renderer::Compact, view, f.area())
+                })
+                .unwrap();
+            // The bottom row of the board, under the title and above the bottom margin
+            (0..10)
+                .map(|x| terminal.backend().buffer()[(x, 20)].bg)
+                .collect::<Vec<_>>()
+        };
+
+        let plain = backgrounds(false);
+        assert!(!plain.contains(&theme.grid));
+        let checkered = backgrounds(true);
+        assert_eq!(checkered[1], theme.piece(TetrominoType::S));
+        assert_ne!(checkered[2], checkered[3]);
+        assert!([theme.empty, theme.grid].contains(&checkered[2]));
+        assert!([theme.empty, theme.grid].contains(&checkered[3]));
+    }
+
+    #[test]
+    fn countdown_shows_seconds_left_as_a_big_digit() {
+        let mut terminal = Terminal::new(TestBackend::new(40, 20)).unwrap();
+        terminal
+            .draw(|f| draw_countdown(f, Duration::from_millis(1500), f.area()))
+            .unwrap();
+
+        let rows: Vec<String> = (0..20)
+            .map(|y| {
+                (0..40)
+                    .map(|x| terminal.backend().buffer()[(x, y)].symbol())
+                    .collect()
+            })
+            .collect();
+        // The middle stroke of a 2 runs full width, its lower half only on the left
+        assert!(rows.iter().any(|row| row.contains("██████")));
+        assert!(rows.iter().any(|row| row.contains("██    ")));
+    }
+
+    #[test]
+    fn frame_rate_follows_draw_interval() {
+        let mut stats = FrameStats::default();
+        let start = Instant::now();
+
+        stats.record_frame(start);
+        assert_eq!(stats.fps, 0.0);
+        stats.record_frame(start + Duration::from_millis(20));
+        assert!((stats.fps - 50.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn latency_runs_from_the_first_press_to_the_next_draw() {
+        let mut latency = LatencyStats::default();
+        let start = Instant::now();
+
+        // A frame with nothing pressed measures nothing
+        latency.frame_drawn(start);
+        latency.key_pressed(start);
+        latency.key_pressed(start + Duration::from_millis(3));
+        latency.frame_drawn(start + Duration::from_millis(6));
+        latency.key_pressed(start + Duration::from_millis(10));
+        latency.frame_drawn(start + Duration::from_millis(110));
+
+        assert_eq!(latency.samples(), 2);
+        assert_eq!(latency.buckets[1], 1);
+        assert_eq!(latency.buckets[LATENCY_BUCKETS - 1], 1);
+        let lines = latency.lines();
+        assert_eq!(lines[0], "Latency avg 53ms max 100ms");
+        assert_eq!(lines.len(), LATENCY_BUCKETS + 1);
+        assert!(lines[2].starts_with("  4ms  ██████████ 1"));
+        assert!(lines[1].ends_with(" 0"));
+    }
 }
@@ -0,0 +1,202 @@
+//! The menu shown before each fresh game, picking its mode, starting level and theme
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use tetris_rs::game::{GameConfig, GameMode, MAX_START_LEVEL};
+use tetris_rs::theme::Theme;
+
+use crate::centered_rect;
+
+const MODES: [GameMode; 5] = [
+    GameMode::Marathon,
+    GameMode::Sprint,
+    GameMode::Dig,
+    GameMode::Survival,
+    GameMode::Zen,
+];
+/// Mode, starting level and theme
+const ROWS: usize = 3;
+
+/// What a key press in the menu asked for, if anything
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuChoice {
+    Start,
+    Quit,
+}
+
+/// Remembers its choices between games, so the next game starts from the last ones
+pub struct Menu {
+    selected: usize,
+    mode: usize,
+    start_level: u32,
+    theme: usize,
+}
+
+impl Menu {
+    /// Starts on the mode and level from `config`, and on the theme named `theme` if
+    /// it is one of [`Theme::NAMES`]
+    pub fn new(config: &GameConfig, theme: Option<&str>) -> Self {
+        Menu {
+            selected: 0,
+            mode: MODES
+                .iter()
+                .position(|&mode| mode == config.mode)
+                .unwrap_or(0),
+            start_level: config.start_level.min(MAX_START_LEVEL),
+            theme: theme
+                .and_then(|name| {
+                    Theme::NAMES
+                        .iter()
+                        .position(|known| known.eq_ignore_ascii_case(name))
+                })
+                .unwrap_or(0),
+        }
+    }
+
+    /// `base` with the chosen mode and starting level
+    pub fn config(&self, base: &GameConfig) -> GameConfig {
+        GameConfig {
+            mode: MODES[self.mode],
+            start_level: self.start_level,
+            ..base.clone()
+        }
+    }
+
+    pub fn theme(&self) -> Theme {
+        Theme::by_name(Theme::NAMES[self.theme]).unwrap_or_default()
+    }
+
+    /// Up and down pick a row, left and right change its value
+    pub fn handle_key(&mut self, code: KeyCode) -> Option<MenuChoice> {
+        match code {
+            KeyCode::Up => self.selected = (self.selected + ROWS - 1) % ROWS,
+            KeyCode::Down => self.selected = (self.selected + 1) % ROWS,
+            KeyCode::Left => self.change(false),
+            KeyCode::Right => self.change(true),
+            KeyCode::Enter | KeyCode::Char(' ') => return Some(MenuChoice::Start),
+            KeyCode::Esc | KeyCode::Char('q') => return Some(MenuChoice::Quit),
+            _ => {}
+        }
+        None
+    }
+
+    /// Modes and themes wrap around, the level stops at either end
+    fn change(&mut self, forward: bool) {
+        let step = |value: usize, count: usize| {
+            if forward {
+                (value + 1) % count
+            } else {
+                (value + count - 1) % count
+            }
+        };
+        match self.selected {
+            0 => self.mode = step(self.mode, MODES.len()),
+            1 if forward => self.start_level = (self.start_level + 1).min(MAX_START_LEVEL),
+            1 => self.start_level = self.start_level.saturating_sub(1),
+            _ => self.theme = step(self.theme, Theme::NAMES.len()),
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame, area: Rect) {
+        let values = [
+            ("Mode", format!("{:?}", MODES[self.mode])),
+            ("Level", self.start_level.to_string()),
+            ("Theme", Theme::NAMES[self.theme].to_string()),
+        ];
+        let mut lines = vec![
+            Line::styled("TETRIS", Style::default().fg(Color::Yellow)),
+            Line::from(""),
+        ];
+        for (row, (label, value)) in values.into_iter().enumerate() {
+            let text = format!("{label:<6}◀ {value:^13} ▶");
+            if row == self.selected {
+                lines.push(Line::styled(text, Style::default().fg(Color::Yellow)));
+            } else {
+                lines.push(Line::from(text));
+            }
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from("Enter: Start  Q: Quit"));
+
+        let menu_area = centered_rect(area, 28, lines.len() as u16 + 2);
+        let menu = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(Clear, menu_area);
+        frame.render_widget(menu, menu_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn arrows_change_the_selected_row() {
+        let mut menu = Menu::new(&GameConfig::default(), Some("monochrome"));
+
+        menu.handle_key(KeyCode::Left);
+        menu.handle_key(KeyCode::Down);
+        menu.handle_key(KeyCode::Right);
+        menu.handle_key(KeyCode::Right);
+        menu.handle_key(KeyCode::Up);
+        menu.handle_key(KeyCode::Up);
+        menu.handle_key(KeyCode::Right);
+
+        let config = menu.config(&GameConfig::default());
+        assert_eq!(config.mode, GameMode::Zen);
+        assert_eq!(config.start_level, 2);
+        assert_eq!(Theme::NAMES[menu.theme], "high-contrast");
+    }
+
+    #[test]
+    fn start_level_stops_at_either_end() {
+        let mut menu = Menu::new(&GameConfig::default(), None);
+        menu.handle_key(KeyCode::Down);
+
+        menu.handle_key(KeyCode::Left);
+        assert_eq!(menu.start_level, 0);
+        for _ in 0..MAX_START_LEVEL + 5 {
+            menu.handle_key(KeyCode::Right);
+        }
+        assert_eq!(menu.start_level, MAX_START_LEVEL);
+    }
+
+    #[test]
+    fn enter_starts_and_escape_quits() {
+        let mut menu = Menu::new(&GameConfig::default(), None);
+        assert_eq!(menu.handle_key(KeyCode::Down), None);
+        assert_eq!(menu.handle_key(KeyCode::Enter), Some(MenuChoice::Start));
+        assert_eq!(menu.handle_key(KeyCode::Esc), Some(MenuChoice::Quit));
+    }
+
+    #[test]
+    fn menu_shows_the_current_choices() {
+        let config = GameConfig {
+            mode: GameMode::Sprint,
+            start_level: 7,
+            ..GameConfig::default()
+        };
+        let menu = Menu::new(&config, Some("Monochrome"));
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal.draw(|f| menu.draw(f, f.area())).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("Sprint"));
+        assert!(screen.contains(" 7 "));
+        assert!(screen.contains("monochrome"));
+    }
+}
@@ -0,0 +1,358 @@
+//! Versus play and spectating over TCP, only built with the `net` feature. Versus
+//! players send each other the events of their own game that matter to the other,
+//! while a broadcasting game sends snapshots of itself to whoever is watching. Every
+//! message is JSON behind its length in four big-endian bytes.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tetris_rs::game::{Game, GameEvent, GameSnapshot};
+
+/// Port `--host` listens on, and `--connect` assumes when the address has none
+pub const DEFAULT_PORT: u16 = 7878;
+/// Port `--broadcast` listens on, and `--spectate` assumes when the address has none
+pub const SPECTATE_PORT: u16 = 7879;
+/// How often a broadcasting game sends its state, often enough to follow the piece
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(50);
+/// Far longer than any event, so a bigger length means the stream is garbled
+const MAX_MESSAGE_LEN: usize = 64 * 1024;
+
+/// The other player, as far as their messages have told
+#[derive(Debug, Default, PartialEq)]
+pub struct Opponent {
+    pub score: u32,
+    pub topped_out: bool,
+    /// The connection dropped
+    pub left: bool,
+}
+
+impl Opponent {
+    fn apply(&mut self, event: GameEvent) {
+        match event {
+            GameEvent::Scored(entry) => {
+                // Scoring again after topping out means they've started a new game
+                if self.topped_out {
+                    *self = Opponent::default();
+                }
+                self.score += entry.points;
+            }
+            GameEvent::GameOver => self.topped_out = true,
+            _ => {}
+        }
+    }
+}
+
+pub struct Versus {
+    stream: TcpStream,
+    /// Filled by a thread blocking on the stream, so the game loop never waits
+    incoming: Receiver<GameEvent>,
+    pub opponent: Opponent,
+}
+
+impl Versus {
+    /// Waits for the other player to connect on `port`
+    pub fn host(port: u16) -> io::Result<Self> {
+        let (stream, _) = TcpListener::bind(("0.0.0.0", port))?.accept()?;
+        Versus::over(stream)
+    }
+
+    /// Connects to a host at `address`, on `DEFAULT_PORT` unless it names a port
+    pub fn connect(address: &str) -> io::Result<Self> {
+        Versus::over(connect(address, DEFAULT_PORT)?)
+    }
+
+    fn over(stream: TcpStream) -> io::Result<Self> {
+        // Messages are tiny and late garbage is worse than a few extra packets
+        stream.set_nodelay(true)?;
+        let incoming = receive_in_background(stream.try_clone()?);
+        Ok(Versus {
+            stream,
+            incoming,
+            opponent: Opponent::default(),
+        })
+    }
+
+    /// Passes on the garbage, scoring and top out among `events`. A failed send
+    /// means the opponent is gone.
+    pub fn send(&mut self, events: &[GameEvent]) {
+        let shared = events.iter().filter(|event| {
+            matches!(
+                event,
+                GameEvent::GarbageSent(_) | GameEvent::Scored(_) | GameEvent::GameOver
+            )
+        });
+        for event in shared {
+            if write_message(&mut self.stream, event).is_err() {
+                self.opponent.left = true;
+                return;
+            }
+        }
+    }
+
+    /// Everything the opponent sent since the last call, already reflected in
+    /// `opponent`
+    pub fn receive(&mut self) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        loop {
+            match self.incoming.try_recv() {
+                Ok(event) => {
+                    self.opponent.apply(event);
+                    events.push(event);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.opponent.left = true;
+                    break;
+                }
+            }
+        }
+        events
+    }
+
+    /// Lines about the opponent for the side panel
+    pub fn status(&self) -> Vec<String> {
+        let mut lines = vec![format!("Opponent: {}", self.opponent.score)];
+        if self.opponent.left {
+            lines.push("Opponent left".to_string());
+        } else if self.opponent.topped_out {
+            lines.push("Opponent topped out!".to_string());
+        }
+        lines
+    }
+}
+
+impl Drop for Versus {
+    /// The reader thread holds its own handle on the socket, so dropping the stream
+    /// alone would leave the connection open
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// Sends snapshots of the game to every spectator that connects
+pub struct Broadcast {
+    /// Added to by a thread accepting connections, so nobody waits on spectators
+    spectators: Arc<Mutex<Vec<TcpStream>>>,
+    last_sent: Option<Instant>,
+}
+
+impl Broadcast {
+    /// Starts accepting spectators on `port` in the background
+    pub fn start(port: u16) -> io::Result<Self> {
+        Ok(Broadcast::on(TcpListener::bind(("0.0.0.0", port))?))
+    }
+
+    fn on(listener: TcpListener) -> Self {
+        let spectators = Arc::new(Mutex::new(Vec::new()));
+        let joined = Arc::clone(&spectators);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = stream.set_nodelay(true);
+                joined.lock().unwrap().push(stream);
+            }
+        });
+        Broadcast {
+            spectators,
+            last_sent: None,
+        }
+    }
+
+    /// Sends the state of `game` if `SNAPSHOT_INTERVAL` has passed since the last
+    /// time, dropping spectators who have gone away
+    pub fn send(&mut self, game: &Game) {
+        if self
+            .last_sent
+            .is_some_and(|at| at.elapsed() < SNAPSHOT_INTERVAL)
+        {
+            return;
+        }
+        self.last_sent = Some(Instant::now());
+        let snapshot = game.snapshot();
+        self.spectators
+            .lock()
+            .unwrap()
+            .retain_mut(|stream| write_message(stream, &snapshot).is_ok());
+    }
+}
+
+/// Watches a broadcasting game
+pub struct Spectator {
+    incoming: Receiver<GameSnapshot>,
+    /// The broadcasting game went away
+    pub ended: bool,
+}
+
+impl Spectator {
+    /// Connects to a broadcast at `address`, on `SPECTATE_PORT` unless it names a port
+    pub fn connect(address: &str) -> io::Result<Self> {
+        Ok(Spectator {
+            incoming: receive_in_background(connect(address, SPECTATE_PORT)?),
+            ended: false,
+        })
+    }
+
+    /// The newest snapshot since the last call, skipping any that arrived in between
+    pub fn latest(&mut self) -> Option<GameSnapshot> {
+        let mut latest = None;
+        loop {
+            match self.incoming.try_recv() {
+                Ok(snapshot) => latest = Some(snapshot),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.ended = true;
+                    break;
+                }
+            }
+        }
+        latest
+    }
+}
+
+/// Connects to `address` as given, or on `default_port` if it's only a host
+fn connect(address: &str, default_port: u16) -> io::Result<TcpStream> {
+    TcpStream::connect(address).or_else(|_| TcpStream::connect((address, default_port)))
+}
+
+/// Reads messages off `stream` on a thread of its own, so the game loop never waits
+/// on the network. The channel disconnects once the stream ends.
+fn receive_in_background<T: DeserializeOwned + Send + 'static>(
+    mut stream: TcpStream,
+) -> Receiver<T> {
+    let (sender, incoming) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(message) = read_message(&mut stream) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+    incoming
+}
+
+fn write_message(writer: &mut impl Write, message: &impl Serialize) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    writer.write_all(&(body.len() as u32).to_be_bytes())?;
+    writer.write_all(&body)
+}
+
+fn read_message<T: DeserializeOwned>(reader: &mut impl Read) -> io::Result<T> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {len} bytes"),
+        ));
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tetris_rs::game::{ScoreEntry, ScoreReason};
+
+    fn scored(points: u32) -> GameEvent {
+        GameEvent::Scored(ScoreEntry {
+            piece: 1,
+            reason: ScoreReason::HardDrop,
+            points,
+        })
+    }
+
+    #[test]
+    fn messages_round_trip_through_their_frames() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &GameEvent::GarbageSent(4)).unwrap();
+        write_message(&mut buffer, &scored(12)).unwrap();
+
+        let mut reader = buffer.as_slice();
+        let first: GameEvent = read_message(&mut reader).unwrap();
+        let second: GameEvent = read_message(&mut reader).unwrap();
+        assert_eq!(first, GameEvent::GarbageSent(4));
+        assert_eq!(second, scored(12));
+        assert!(read_message::<GameEvent>(&mut reader).is_err());
+
+        let oversized = (MAX_MESSAGE_LEN as u32 + 1).to_be_bytes();
+        assert!(read_message::<GameEvent>(&mut oversized.as_slice()).is_err());
+    }
+
+    #[test]
+    fn opponent_adds_up_score_and_starts_over_after_topping_out() {
+        let mut opponent = Opponent::default();
+        opponent.apply(scored(100));
+        opponent.apply(GameEvent::GarbageSent(2));
+        opponent.apply(scored(20));
+        opponent.apply(GameEvent::GameOver);
+        assert_eq!(opponent.score, 120);
+        assert!(opponent.topped_out);
+
+        opponent.apply(scored(5));
+        assert_eq!(opponent.score, 5);
+        assert!(!opponent.topped_out);
+    }
+
+    #[test]
+    fn only_what_the_opponent_needs_goes_over_the_wire() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut client = Versus::connect(&format!("127.0.0.1:{port}")).unwrap();
+        let mut host = Versus::over(listener.accept().unwrap().0).unwrap();
+
+        client.send(&[
+            GameEvent::Rotated,
+            GameEvent::GarbageSent(3),
+            GameEvent::PieceLocked,
+            GameEvent::GameOver,
+        ]);
+
+        let mut received = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while received.len() < 2 && Instant::now() < deadline {
+            received.extend(host.receive());
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(received, [GameEvent::GarbageSent(3), GameEvent::GameOver]);
+        assert!(host.opponent.topped_out);
+
+        drop(client);
+        while !host.opponent.left && Instant::now() < deadline {
+            host.receive();
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(host.opponent.left);
+    }
+
+    #[test]
+    fn spectators_see_the_latest_snapshot() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut broadcast = Broadcast::on(listener);
+        let mut spectator = Spectator::connect(&format!("127.0.0.1:{port}")).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while broadcast.spectators.lock().unwrap().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        let mut game = Game::with_seed(3);
+        broadcast.send(&game);
+        // Too soon after the last one to go out
+        game.hard_drop();
+        broadcast.send(&game);
+
+        let mut latest = None;
+        while latest.is_none() && Instant::now() < deadline {
+            latest = spectator.latest();
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(latest, Some(Game::with_seed(3).snapshot()));
+    }
+}
@@ -0,0 +1,123 @@
+//! Ways of picking which piece comes next. A game deals every piece through one of
+//! these, drawing on its own seeded generator so the sequence stays reproducible.
+
+use crate::game::TetrominoType;
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Pieces the history randomizer tries not to deal again
+const HISTORY_LEN: usize = 4;
+/// Draws the history randomizer makes looking for a piece outside its history
+const HISTORY_ROLLS: usize = 6;
+
+/// How the next piece is picked, along with whatever it remembers of the pieces
+/// dealt so far. A config holds a fresh one for each game to start from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Randomizer {
+    /// One of each piece in a shuffled bag, refilled once empty, so there are never
+    /// long droughts or floods. Seven per bag for the tetrominoes, twelve for the
+    /// pentominoes.
+    Bag { bag: Vec<TetrominoType> },
+    /// Every piece equally likely every time, with nothing stopping repeats
+    Uniform,
+    /// TGM style: redraws up to `HISTORY_ROLLS` times while the piece is among the
+    /// last `HISTORY_LEN` dealt. Unlike TGM, failing every redraw still never deals
+    /// the last piece twice in a row.
+    History { recent: VecDeque<TetrominoType> },
+}
+
+impl Default for Randomizer {
+    fn default() -> Self {
+        Randomizer::Bag { bag: Vec::new() }
+    }
+}
+
+impl Randomizer {
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bag" | "bag7" => Some(Randomizer::default()),
+            "uniform" | "random" => Some(Randomizer::Uniform),
+            "history" | "tgm" => Some(Randomizer::History {
+                recent: VecDeque::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Deals the next piece, one of `pieces`
+    pub fn next(&mut self, pieces: &[TetrominoType], rng: &mut ChaCha12Rng) -> TetrominoType {
+        match self {
+            Randomizer::Bag { bag } => {
+                if bag.is_empty() {
+                    bag.extend_from_slice(pieces);
+                    bag.shuffle(rng);
+                }
+                bag.pop().unwrap()
+            }
+            Randomizer::Uniform => *pieces.choose(rng).unwrap(),
+            Randomizer::History { recent } => {
+                let piece = (0..HISTORY_ROLLS)
+                    .map(|_| *pieces.choose(rng).unwrap())
+                    .find(|piece| !recent.contains(piece))
+                    .or_else(|| {
+                        pieces
+                            .iter()
+                            .filter(|&piece| recent.back() != Some(piece))
+                            .choose(rng)
+                            .copied()
+                    })
+                    // Only a set of one piece has nothing else to deal
+                    .unwrap_or(pieces[0]);
+                recent.push_back(piece);
+                if recent.len() > HISTORY_LEN {
+                    recent.pop_front();
+                }
+                piece
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn deal(randomizer: &mut Randomizer, count: usize) -> Vec<TetrominoType> {
+        let mut rng = ChaCha12Rng::seed_from_u64(5);
+        (0..count)
+            .map(|_| randomizer.next(&TetrominoType::TETROMINOES, &mut rng))
+            .collect()
+    }
+
+    #[test]
+    fn bag_deals_all_seven_before_any_repeat() {
+        let pieces = deal(&mut Randomizer::default(), 7 * 50);
+        for bag in pieces.chunks(7) {
+            for piece in TetrominoType::TETROMINOES {
+                assert_eq!(bag.iter().filter(|&&dealt| dealt == piece).count(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn history_never_deals_the_same_piece_twice_in_a_row() {
+        let mut randomizer = Randomizer::by_name("history").unwrap();
+        let pieces = deal(&mut randomizer, 2000);
+        assert!(pieces.windows(2).all(|pair| pair[0] != pair[1]));
+        for piece in TetrominoType::TETROMINOES {
+            assert!(pieces.contains(&piece));
+        }
+    }
+
+    #[test]
+    fn uniform_deals_every_piece_and_repeats_them() {
+        let pieces = deal(&mut Randomizer::Uniform, 2000);
+        for piece in TetrominoType::TETROMINOES {
+            assert!(pieces.contains(&piece));
+        }
+        assert!(pieces.windows(2).any(|pair| pair[0] == pair[1]));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::game::{Game, GameConfig, GameEvent, SIMULATION_STEP};
+use crate::input::Action;
+use crate::settings::app_dir;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Directory that finished games' replays are written to
+pub fn replay_dir() -> Option<PathBuf> {
+    app_dir().map(|dir| dir.join("replays"))
+}
+
+/// Every action of one game with its time on the game clock, plus the config
+/// and seed needed to recreate the same piece sequence
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub config: GameConfig,
+    pub inputs: Vec<(Duration, Action)>,
+    /// [`Game::state_hash`] of the game as it ended, missing from replays saved
+    /// before the hash existed
+    #[serde(default)]
+    pub final_hash: Option<u64>,
+}
+
+impl Replay {
+    /// Starts an empty recording of `game`
+    pub fn new(game: &Game) -> Self {
+        Replay {
+            config: GameConfig {
+                seed: Some(game.seed),
+                ..game.config.clone()
+            },
+            inputs: Vec::new(),
+            final_hash: None,
+        }
+    }
+
+    pub fn record(&mut self, at: Duration, action: Action) {
+        self.inputs.push((at, action));
+    }
+
+    /// Notes how `game` ended, for playback to check against
+    pub fn finish(&mut self, game: &Game) {
+        self.final_hash = Some(game.state_hash());
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Replay> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+/// Feeds a recorded game's actions back in once the game clock reaches them
+pub struct Playback {
+    replay: Replay,
+    position: usize,
+    /// Real time handed to `advance` that doesn't make up a whole step yet
+    unplayed: Duration,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Playback {
+            replay,
+            position: 0,
+            unplayed: Duration::ZERO,
+        }
+    }
+
+    /// A fresh game to play the recording on
+    pub fn game(&self) -> Game {
+        Game::with_config(self.replay.config.clone())
+    }
+
+    /// Runs `game` on by `dt` of real time, one simulation step at a time, handing
+    /// it each recorded action right after the step it was recorded after. Stepping
+    /// the game here rather than in `Game::update` keeps actions from landing a
+    /// frame late, so it plays out exactly as it was recorded.
+    pub fn advance(&mut self, game: &mut Game, dt: Duration) -> Vec<GameEvent> {
+        self.unplayed += dt;
+        let mut events = self.apply_due(game);
+        while self.unplayed >= SIMULATION_STEP {
+            self.unplayed -= SIMULATION_STEP;
+            events.extend(game.step());
+            events.extend(self.apply_due(game));
+        }
+        events
+    }
+
+    fn apply_due(&mut self, game: &mut Game) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        while let Some(action) = self.next_due(game.elapsed()) {
+            events.extend(game.handle_action(action));
+        }
+        events
+    }
+
+    /// The next recorded action due by `elapsed` game time, if any. Call repeatedly
+    /// until it returns `None` to catch up on everything that's due.
+    fn next_due(&mut self, elapsed: Duration) -> Option<Action> {
+        let &(at, action) = self.replay.inputs.get(self.position)?;
+        if at > elapsed {
+            return None;
+        }
+        self.position += 1;
+        Some(action)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.replay.inputs.len()
+    }
+
+    /// Whether the played back `game` ended the way the recorded one did, or `None`
+    /// for replays without a hash to compare. Playback is step for step, so a
+    /// mismatch means the file was edited or recorded by a version with different
+    /// rules.
+    pub fn matches(&self, game: &Game) -> Option<bool> {
+        self.replay.final_hash.map(|hash| hash == game.state_hash())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    #[test]
+    fn playback_recreates_the_recorded_game() {
+        let mut game = Game::with_seed(9);
+        game.state = GameState::Playing;
+        let mut replay = Replay::new(&game);
+        for action in [
+            Action::MoveLeft,
+            Action::RotateCW,
+            Action::HardDrop,
+            Action::HardDrop,
+        ] {
+            game.handle_action(action);
+            replay.record(Duration::ZERO, action);
+        }
+
+        let mut playback = Playback::new(replay);
+        let mut replayed = playback.game();
+        replayed.state = GameState::Playing;
+        while let Some(action) = playback.next_due(Duration::ZERO) {
+            replayed.handle_action(action);
+        }
+
+        assert!(playback.is_finished());
+        assert_eq!(replayed.board, game.board);
+        assert_eq!(replayed.current_piece, game.current_piece);
+    }
+
+    #[test]
+    fn playback_checks_the_end_against_the_recorded_hash() {
+        let mut game = Game::with_seed(9);
+        let mut replay = Replay::new(&game);
+        game.hard_drop();
+        replay.record(Duration::ZERO, Action::HardDrop);
+        let unfinished = Playback::new(Replay::new(&game));
+        assert_eq!(unfinished.matches(&game), None);
+
+        replay.finish(&game);
+        let json = serde_json::to_string(&replay).unwrap();
+        let playback = Playback::new(serde_json::from_str(&json).unwrap());
+        assert_eq!(playback.matches(&game), Some(true));
+        game.score += 100;
+        assert_eq!(playback.matches(&game), Some(false));
+    }
+
+    #[test]
+    fn a_recorded_game_replays_to_the_same_state() {
+        // Uneven frames with a few actions between them, the way a live game goes
+        let actions = [
+            Action::MoveLeft,
+            Action::MoveLeft,
+            Action::RotateCW,
+            Action::MoveLeft,
+            Action::HardDrop,
+            Action::Hold,
+            Action::MoveRight,
+            Action::MoveRight,
+            Action::MoveRight,
+            Action::RotateCCW,
+            Action::SoftDrop,
+            Action::HardDrop,
+        ];
+        let mut game = Game::with_seed(5);
+        let mut replay = Replay::new(&game);
+        for frame in 0..1200u64 {
+            game.update(Duration::from_micros(2_000 + frame % 7 * 2_300));
+            if frame % 9 == 0 {
+                let action = actions[frame as usize / 9 % actions.len()];
+                replay.record(game.elapsed(), action);
+                game.handle_action(action);
+            }
+            if frame == 500 || frame == 520 {
+                replay.record(game.elapsed(), Action::Pause);
+                game.handle_action(Action::Pause);
+            }
+        }
+        // Paused, so playback can run on past the end without changing anything
+        replay.record(game.elapsed(), Action::Pause);
+        game.handle_action(Action::Pause);
+        assert!(game.pieces_placed > 5 && !game.is_over());
+
+        replay.finish(&game);
+
+        let mut playback = Playback::new(replay);
+        let mut replayed = playback.game();
+        while !playback.is_finished() {
+            playback.advance(&mut replayed, Duration::from_millis(16));
+        }
+
+        assert_eq!(playback.matches(&replayed), Some(true));
+        assert_eq!(replayed.snapshot(), game.snapshot());
+        assert_eq!(replayed.elapsed(), game.elapsed());
+        assert_eq!(replayed.state_hash(), game.state_hash());
+        assert_eq!(replayed.lock_timer, game.lock_timer);
+        assert_eq!(replayed.gravity_accumulator, game.gravity_accumulator);
+    }
+
+    #[test]
+    fn a_topped_out_replay_matches_its_hash_after_the_animation() {
+        let mut game = Game::with_seed(3);
+        let mut replay = Replay::new(&game);
+        for frame in 0u64.. {
+            game.update(Duration::from_micros(3_000 + frame % 5 * 4_100));
+            if game.is_over() {
+                break;
+            }
+            if frame % 20 == 0 {
+                replay.record(game.elapsed(), Action::HardDrop);
+                game.handle_action(Action::HardDrop);
+            }
+        }
+        game.skip_top_out();
+        replay.finish(&game);
+
+        let mut playback = Playback::new(replay);
+        let mut replayed = playback.game();
+        while !replayed.is_over() {
+            playback.advance(&mut replayed, Duration::from_millis(16));
+        }
+        replayed.skip_top_out();
+        assert_eq!(playback.matches(&replayed), Some(true));
+    }
+
+    #[test]
+    fn playback_waits_for_each_timestamp() {
+        let game = Game::with_seed(9);
+        let mut replay = Replay::new(&game);
+        replay.record(Duration::from_secs(1), Action::MoveLeft);
+        replay.record(Duration::from_secs(2), Action::MoveRight);
+        let mut playback = Playback::new(replay);
+
+        assert_eq!(playback.next_due(Duration::from_millis(500)), None);
+        assert_eq!(
+            playback.next_due(Duration::from_secs(1)),
+            Some(Action::MoveLeft)
+        );
+        assert_eq!(playback.next_due(Duration::from_secs(1)), None);
+        assert_eq!(
+            playback.next_due(Duration::from_secs(3)),
+            Some(Action::MoveRight)
+        );
+        assert!(playback.is_finished());
+    }
+}
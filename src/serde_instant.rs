@@ -0,0 +1,60 @@
+//! Serde support for `Instant`, which has no absolute value to write out. Instants
+//! are stored as their age when saved and rebuilt relative to the moment of
+//! loading, so timers and the game clock carry on where they left off.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::time::{Duration, Instant};
+
+fn from_age(age: Duration) -> Instant {
+    let now = Instant::now();
+    // An age reaching back past the clock's origin (e.g. saved before a reboot)
+    // can't be represented, so it restarts from now
+    now.checked_sub(age).unwrap_or(now)
+}
+
+pub fn serialize<S: Serializer>(instant: &Instant, serializer: S) -> Result<S::Ok, S::Error> {
+    instant.elapsed().serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Instant, D::Error> {
+    Duration::deserialize(deserializer).map(from_age)
+}
+
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        instant: &Option<Instant>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        instant.map(|at| at.elapsed()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Instant>, D::Error> {
+        Option::<Duration>::deserialize(deserializer).map(|age| age.map(from_age))
+    }
+}
+
+/// For values paired with the time they were set, like `Option<(TSpin, Instant)>`
+pub mod option_pair {
+    use super::*;
+
+    pub fn serialize<S: Serializer, T: Serialize>(
+        value: &Option<(T, Instant)>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value
+            .as_ref()
+            .map(|(inner, at)| (inner, at.elapsed()))
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<Option<(T, Instant)>, D::Error> {
+        Option::<(T, Duration)>::deserialize(deserializer)
+            .map(|value| value.map(|(inner, age)| (inner, from_age(age))))
+    }
+}
@@ -0,0 +1,162 @@
+use crate::input::{HandlingConfig, KeyConfig};
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// Persistent user preferences, read from `config.toml` in the app's config directory
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub keys: KeyConfig,
+    pub handling: HandlingConfig,
+    /// Name of a built-in color theme, classic if unset or unknown
+    pub theme: Option<String>,
+    /// How the board is drawn, `"blocks"`, `"compact"` or `"half-blocks"`, blocks if unset or unknown
+    pub renderer: Option<String>,
+    /// How the ghost piece is drawn, `"dim"`, `"outline"` or `"none"`, dim if unset or unknown
+    pub ghost: Option<String>,
+    /// Terminal rows per board row when drawing blocks, 2 if unset
+    pub vertical_scale: Option<usize>,
+    /// Terminal columns per board cell when drawing blocks, from 2 to 4, 4 if unset
+    pub cell_width: Option<usize>,
+    /// Draw a falling piece between rows as gravity pulls it, except with half blocks
+    pub smooth_falling: bool,
+    /// Start with the grid on empty cells shown
+    pub grid: bool,
+    /// Leave empty cells in the terminal's background color instead of the theme's
+    pub transparent_board: bool,
+    /// Start with the side panel hidden, leaving the whole width to the board
+    pub minimal: bool,
+    /// Start with the board mirrored left to right
+    pub mirror: bool,
+    /// Start with the controls help shown; if unset, it's shown on the first run only
+    pub help: Option<bool>,
+}
+
+/// Directory holding the config file and saved data such as the high score
+pub fn app_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("tetris-rs"))
+}
+
+fn high_score_path() -> Option<PathBuf> {
+    app_dir().map(|dir| dir.join("highscore"))
+}
+
+/// Reads the saved high score, defaulting to 0 if the file is missing or corrupt
+pub fn load_high_score() -> u32 {
+    high_score_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| contents.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+pub fn save_high_score(high_score: u32) {
+    let Some(path) = high_score_path() else {
+        return;
+    };
+
+    // Failing to persist the high score shouldn't interrupt the game
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(path, high_score.to_string());
+}
+
+/// Whether this is the first run, judged by a marker file in the app directory that
+/// is left behind the first time this is asked
+pub fn first_run() -> bool {
+    let Some(path) = app_dir().map(|dir| dir.join("first_run_done")) else {
+        return false;
+    };
+    if path.exists() {
+        return false;
+    }
+
+    // Failing to leave the marker only means the next run counts as the first again
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(path, "");
+    true
+}
+
+impl Settings {
+    /// Loads the config file, falling back to defaults if it's missing or malformed
+    pub fn load() -> Self {
+        app_dir()
+            .and_then(|dir| fs::read_to_string(dir.join("config.toml")).ok())
+            .and_then(|contents| Self::from_toml(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_key_section() {
+        let settings = Settings::from_toml(
+            r#"
+            [keys]
+            move_left = "a"
+            hard_drop = "Enter"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(settings.keys.move_left.as_deref(), Some("a"));
+        assert_eq!(settings.keys.hard_drop.as_deref(), Some("Enter"));
+        assert!(settings.keys.hold.is_none());
+    }
+
+    #[test]
+    fn empty_file_uses_defaults() {
+        let settings = Settings::from_toml("").unwrap();
+        assert!(settings.keys.move_left.is_none());
+        assert!(settings.theme.is_none());
+    }
+
+    #[test]
+    fn reads_handling_section() {
+        let settings = Settings::from_toml(
+            r#"
+            [handling]
+            das_ms = 120
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(settings.handling.das_ms, 120);
+        assert_eq!(settings.handling.arr_ms, HandlingConfig::default().arr_ms);
+    }
+
+    #[test]
+    fn reads_theme_name() {
+        let settings = Settings::from_toml("theme = \"monochrome\"").unwrap();
+        assert_eq!(settings.theme.as_deref(), Some("monochrome"));
+    }
+
+    #[test]
+    fn reads_renderer_name() {
+        let settings = Settings::from_toml("renderer = \"half-blocks\"").unwrap();
+        assert_eq!(settings.renderer.as_deref(), Some("half-blocks"));
+    }
+
+    #[test]
+    fn reads_help_toggle() {
+        let settings = Settings::from_toml("help = false").unwrap();
+        assert_eq!(settings.help, Some(false));
+        assert_eq!(Settings::from_toml("").unwrap().help, None);
+    }
+
+    #[test]
+    fn reads_vertical_scale() {
+        let settings = Settings::from_toml("vertical_scale = 3").unwrap();
+        assert_eq!(settings.vertical_scale, Some(3));
+    }
+}
@@ -0,0 +1,52 @@
+//! Short synthesized sound effects, only built with the `sound` feature
+
+use rodio::source::{Function, SignalGenerator, Source};
+use rodio::{DeviceSinkBuilder, MixerDeviceSink};
+use std::num::NonZero;
+use std::time::Duration;
+use tetris_rs::game::GameEvent;
+
+const SAMPLE_RATE: NonZero<u32> = NonZero::new(44_100).unwrap();
+const VOLUME: f32 = 0.1;
+
+pub struct Sound {
+    /// `None` when no audio device could be opened, which makes every effect a no-op
+    sink: Option<MixerDeviceSink>,
+}
+
+impl Sound {
+    pub fn new() -> Self {
+        let sink = DeviceSinkBuilder::open_default_sink().ok().map(|mut sink| {
+            // Dropping the sink would otherwise print over the restored terminal
+            sink.log_on_drop(false);
+            sink
+        });
+        Sound { sink }
+    }
+
+    pub fn play(&self, event: GameEvent) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+
+        // (frequency in Hz, length in ms, waveform)
+        let (frequency, millis, function) = match event {
+            GameEvent::Rotated => (660.0, 30, Function::Square),
+            GameEvent::HardDropped => (110.0, 60, Function::Triangle),
+            GameEvent::PieceLocked => (220.0, 40, Function::Triangle),
+            GameEvent::LinesCleared(4..) => (1046.0, 300, Function::Square),
+            GameEvent::LinesCleared(_) => (784.0, 150, Function::Sine),
+            GameEvent::TSpin(_) => (880.0, 120, Function::Square),
+            GameEvent::LevelUp(_) => (1318.0, 250, Function::Sine),
+            GameEvent::PerfectClear => (1568.0, 400, Function::Square),
+            // Every scoring action already has a sound of its own
+            GameEvent::Scored(_) | GameEvent::GarbageSent(_) => return,
+            GameEvent::GameOver => (98.0, 800, Function::Sawtooth),
+        };
+        sink.mixer().add(
+            SignalGenerator::new(SAMPLE_RATE, frequency, function)
+                .amplify(VOLUME)
+                .take_duration(Duration::from_millis(millis)),
+        );
+    }
+}
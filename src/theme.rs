@@ -0,0 +1,130 @@
+use crate::game::TetrominoType;
+use ratatui::style::Color;
+
+/// Colors used to draw the game, so the palette can suit the player's eyes or terminal
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Piece colors, indexed by `TetrominoType::color`
+    pub pieces: [Color; 7],
+    pub empty: Color,
+    /// Every other empty cell when the grid is shown, a shade off `empty`
+    pub grid: Color,
+    pub garbage: Color,
+    /// Border color for every panel, or `None` to keep each panel's own color
+    pub border: Option<Color>,
+    pub ghost: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::classic()
+    }
+}
+
+impl Theme {
+    /// Names accepted by `by_name`, for help text
+    pub const NAMES: [&'static str; 3] = ["classic", "monochrome", "high-contrast"];
+
+    pub fn classic() -> Self {
+        Theme {
+            pieces: [
+                Color::Cyan,    // I
+                Color::Yellow,  // O
+                Color::Magenta, // T
+                Color::White,   // L
+                Color::Blue,    // J
+                Color::Green,   // S
+                Color::Red,     // Z
+            ],
+            empty: Color::Gray,
+            grid: Color::Indexed(249),
+            garbage: Color::Indexed(244), // Mid gray, between the board and the ghost
+            border: None,
+            ghost: Color::DarkGray,
+        }
+    }
+
+    /// Every piece the same, for terminals without color
+    pub fn monochrome() -> Self {
+        Theme {
+            pieces: [Color::White; 7],
+            empty: Color::Black,
+            grid: Color::Indexed(234),
+            garbage: Color::Gray,
+            border: Some(Color::White),
+            ghost: Color::DarkGray,
+        }
+    }
+
+    /// Bright pieces on a black board
+    pub fn high_contrast() -> Self {
+        Theme {
+            pieces: [
+                Color::LightCyan,    // I
+                Color::LightYellow,  // O
+                Color::LightMagenta, // T
+                Color::White,        // L
+                Color::LightBlue,    // J
+                Color::LightGreen,   // S
+                Color::LightRed,     // Z
+            ],
+            empty: Color::Black,
+            grid: Color::Indexed(234),
+            garbage: Color::DarkGray,
+            border: Some(Color::White),
+            ghost: Color::Gray,
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "classic" => Some(Theme::classic()),
+            "monochrome" => Some(Theme::monochrome()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+
+    /// The same theme with empty cells left to the terminal's own background, so the
+    /// board blends in with it on light and dark terminals alike
+    pub fn transparent(self) -> Self {
+        Theme {
+            empty: Color::Reset,
+            ..self
+        }
+    }
+
+    pub fn piece(&self, piece_type: TetrominoType) -> Color {
+        self.pieces[piece_type.color()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_name_resolves() {
+        for name in Theme::NAMES {
+            assert!(Theme::by_name(name).is_some(), "{name} should be a theme");
+        }
+        assert_eq!(
+            Theme::by_name("High-Contrast"),
+            Some(Theme::high_contrast())
+        );
+        assert_eq!(Theme::by_name("neon"), None);
+    }
+
+    #[test]
+    fn transparent_only_changes_the_empty_cells() {
+        let theme = Theme::high_contrast().transparent();
+        assert_eq!(theme.empty, Color::Reset);
+        assert_eq!(
+            Theme {
+                empty: Color::Black,
+                ..theme
+            },
+            Theme::high_contrast()
+        );
+    }
+}
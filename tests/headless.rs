@@ -0,0 +1,82 @@
+//! Drives a game purely through the library API, with no terminal involved
+
+use tetris_rs::game::Game;
+use tetris_rs::input::Action;
+
+#[test]
+fn hard_drops_stack_pieces_in_the_snapshot() {
+    let mut game = Game::with_seed(42);
+    let first = game.snapshot();
+    assert!(first.board.iter().flatten().all(|&filled| !filled));
+
+    for _ in 0..5 {
+        game.hard_drop();
+    }
+
+    // Spawned in the middle and dropped straight down, nothing can clear yet
+    let snapshot = game.snapshot();
+    let filled = snapshot
+        .board
+        .iter()
+        .flatten()
+        .filter(|&&filled| filled)
+        .count();
+    assert_eq!(filled, 5 * 4);
+    assert_eq!(snapshot.piece_type, first.next[4]);
+    assert_eq!(snapshot.score, game.score);
+    assert!(!snapshot.game_over);
+}
+
+#[test]
+fn moves_and_rotations_show_up_in_the_snapshot() {
+    let mut game = Game::with_seed(42);
+    let before = game.snapshot();
+
+    assert!(game.move_piece(-1, 0));
+    game.rotate_piece();
+    game.tick();
+
+    let after = game.snapshot();
+    assert_eq!(after.rotation, (before.rotation + 1) % 4);
+    assert!(after.piece_y > before.piece_y);
+    assert_ne!(after.shape, before.shape);
+}
+
+#[test]
+fn a_shown_snapshot_looks_like_the_game_it_came_from() {
+    let mut game = Game::with_seed(42);
+    for _ in 0..3 {
+        game.hard_drop();
+    }
+    game.move_piece(1, 0);
+    game.hold();
+
+    // Sent as JSON to a spectator, whose own game has a different board and pieces
+    let json = serde_json::to_string(&game.snapshot()).unwrap();
+    let mut spectator = Game::with_seed(7);
+    spectator.show_snapshot(&serde_json::from_str(&json).unwrap());
+
+    assert_eq!(spectator.snapshot(), game.snapshot());
+    assert!(!spectator.is_counting_down());
+}
+
+// Scoring, topping out and restarting are all pure game logic, so none of them
+// should reach the player's saved high score
+#[cfg(target_os = "linux")]
+#[test]
+fn restarting_a_game_leaves_the_high_score_file_alone() {
+    let config_home = std::env::temp_dir().join(format!("tetris-rs-config-{}", std::process::id()));
+    std::env::set_var("XDG_CONFIG_HOME", &config_home);
+
+    let mut game = Game::with_seed(42);
+    while !game.is_over() {
+        game.hard_drop();
+    }
+    let high_score = game.high_score;
+    assert!(high_score > 0);
+    game.handle_action(Action::Restart);
+    game.hard_drop();
+
+    assert_eq!(game.high_score, high_score);
+    assert!(!config_home.exists());
+}